@@ -1,12 +1,66 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use hello::{
     quote_to_polars_df_from_rows_cols, quote_to_polars_df_from_series_raghu,
     quote_to_polars_df_from_series_v0, quote_to_polars_df_from_series_v1,
     quote_to_polars_df_from_series_v2, quote_to_polars_df_from_series_v3,
+    quote_to_polars_df_numeric_only,
 };
-use hello::{read_json_from_file, Quotes};
+use hello::{read_json_from_file, Depth, OhlcInner, OrderDepth, Quotes, QuotesData};
+use polars::prelude::{DataFrame, PolarsError};
 use std::hint::black_box;
 
+type Conversion = fn(Quotes) -> Result<DataFrame, PolarsError>;
+
+const VARIANTS: [(&str, Conversion); 6] = [
+    ("raghu", quote_to_polars_df_from_series_raghu),
+    ("v0", quote_to_polars_df_from_series_v0),
+    ("v1", quote_to_polars_df_from_series_v1),
+    ("v2", quote_to_polars_df_from_series_v2),
+    ("v3", quote_to_polars_df_from_series_v3),
+    ("rows_cols", quote_to_polars_df_from_rows_cols),
+];
+
+// Deterministic instruments with a full five-level book on each side.
+fn synthetic_quotes(n: usize) -> Quotes {
+    (0..n)
+        .map(|i| {
+            let price = 100.0 + (i % 1000) as f64 * 0.05;
+            let level = |offset: f64, depth: u64| OrderDepth {
+                price: price + offset,
+                quantity: 100 * (depth + 1),
+                orders: depth + 1,
+            };
+            let q = QuotesData {
+                instrument_token: 1_000_000 + i as u64,
+                timestamp: "2021-06-08 15:45:56".to_owned(),
+                last_trade_time: "2021-06-08 15:45:52".to_owned(),
+                last_price: price,
+                last_quantity: (i % 50) as u64,
+                buy_quantity: 10 * i as u64,
+                sell_quantity: 12 * i as u64,
+                volume: 1000 * i as u64,
+                average_price: price - 0.5,
+                oi: (i % 7) as u64 * 1000,
+                net_change: 0.25,
+                lower_circuit_limit: price * 0.8,
+                upper_circuit_limit: price * 1.2,
+                ohlc: Some(OhlcInner {
+                    open: price - 1.0,
+                    high: price + 2.0,
+                    low: price - 2.0,
+                    close: price - 0.25,
+                }),
+                depth: Depth {
+                    buy: (0..5).map(|d| level(-0.05 * (d + 1) as f64, d)).collect(),
+                    sell: (0..5).map(|d| level(0.05 * (d + 1) as f64, d)).collect(),
+                },
+                ..QuotesData::default()
+            };
+            (format!("NSE:SYM{i}"), q)
+        })
+        .collect()
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json").unwrap();
     let quotes: Quotes = serde_json::from_reader(jsonfile).unwrap();
@@ -28,6 +82,34 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("quote_to_polars_df_from_rows_cols", |b| {
         b.iter(|| quote_to_polars_df_from_rows_cols(quotes.clone()).unwrap())
     });
+
+    // One group per variant so each report plots time against instrument count.
+    let sizes = [10, 100, 1_000, 10_000];
+    let synthetic: Vec<Quotes> = sizes.iter().map(|&n| synthetic_quotes(n)).collect();
+    for (name, convert) in VARIANTS {
+        let mut group = c.benchmark_group(format!("scaling/{name}"));
+        for (n, quotes) in sizes.iter().zip(&synthetic) {
+            group.bench_with_input(BenchmarkId::from_parameter(n), quotes, |b, quotes| {
+                b.iter_batched(|| quotes.clone(), convert, BatchSize::LargeInput)
+            });
+        }
+        group.finish();
+    }
+
+    let mut group = c.benchmark_group("numeric_only_vs_v1");
+    for (n, quotes) in sizes.iter().zip(&synthetic) {
+        group.bench_with_input(BenchmarkId::new("v1", n), quotes, |b, quotes| {
+            b.iter_batched(
+                || quotes.clone(),
+                quote_to_polars_df_from_series_v1,
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("numeric_only", n), quotes, |b, quotes| {
+            b.iter(|| quote_to_polars_df_numeric_only(black_box(quotes)).unwrap())
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
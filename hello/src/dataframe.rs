@@ -0,0 +1,4246 @@
+use crate::read_json_from_file;
+#[cfg(feature = "gzip")]
+use crate::read_json_from_gz;
+use crate::types::*;
+use chrono::NaiveDateTime;
+use polars::datatypes::AnyValue;
+use polars::frame::row::Row;
+use polars::prelude::NamedFrom;
+use polars::prelude::{
+    ArrowSchema, CategoricalOrdering, CompatLevel, CsvReadOptions, CsvWriter, DataFrame,
+    DataFrameJoinOps, DataType, Field, Float64Chunked, IntoSeries, IpcReader, IpcWriter, JoinArgs,
+    JoinType, JsonFormat, JsonReader, ParquetWriter, PolarsError, RoundSeries, Schema, Series,
+    TimeUnit,
+};
+use polars::prelude::{SerReader, SerWriter};
+use serde::de::{self, MapAccess, Visitor};
+use serde::Deserializer;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Cursor;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub fn quotes_df_from_slice(bytes: &[u8]) -> Result<DataFrame, Box<dyn Error>> {
+    let quotes = stage!("parse", Quotes::from_slice(bytes))?;
+    Ok(DataFrame::new(quote_series(&quotes))?)
+}
+
+#[cfg(feature = "gzip")]
+pub fn quotes_df_from_gz_file<P: AsRef<Path>>(path: P) -> Result<DataFrame, Box<dyn Error>> {
+    let reader = read_json_from_gz(path)?;
+    let quotes: Quotes = stage!("parse", serde_json::from_reader(reader))?;
+    Ok(DataFrame::new(quote_series(&quotes))?)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(instruments = quote.instruments.len()))
+)]
+pub fn quote_to_polars_df_from_series_raghu(quote: Quotes) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut symbols = Vec::with_capacity(len);
+    let mut instrument_tokens = Vec::with_capacity(len);
+    let mut timestamps = Vec::with_capacity(len);
+    let mut last_trade_times = Vec::with_capacity(len);
+    let mut last_prices = Vec::with_capacity(len);
+    let mut last_quantities = Vec::with_capacity(len);
+    let mut buy_quantities = Vec::with_capacity(len);
+    let mut sell_quantities = Vec::with_capacity(len);
+    let mut volumes = Vec::with_capacity(len);
+    let mut average_prices = Vec::with_capacity(len);
+    let mut ois = Vec::with_capacity(len);
+    let mut oi_day_highs = Vec::with_capacity(len);
+    let mut oi_day_lows = Vec::with_capacity(len);
+    let mut net_changes = Vec::with_capacity(len);
+    let mut lower_circuit_limits = Vec::with_capacity(len);
+    let mut upper_circuit_limits = Vec::with_capacity(len);
+    let mut opens = Vec::with_capacity(len);
+    let mut highs = Vec::with_capacity(len);
+    let mut lows = Vec::with_capacity(len);
+    let mut closes = Vec::with_capacity(len);
+
+    stage!("fill_columns", {
+        for (symbol, q) in quote.instruments {
+            symbols.push(symbol);
+            instrument_tokens.push(q.instrument_token);
+            timestamps.push(q.timestamp.clone());
+            last_trade_times.push(q.last_trade_time.clone());
+            last_prices.push(q.last_price);
+            last_quantities.push(q.last_quantity);
+            buy_quantities.push(q.buy_quantity);
+            sell_quantities.push(q.sell_quantity);
+            volumes.push(q.volume);
+            average_prices.push(q.average_price);
+            ois.push(q.oi);
+            oi_day_highs.push(q.oi_day_high);
+            oi_day_lows.push(q.oi_day_low);
+            net_changes.push(q.net_change);
+            lower_circuit_limits.push(q.lower_circuit_limit);
+            upper_circuit_limits.push(q.upper_circuit_limit);
+            opens.push(q.ohlc.as_ref().map(|o| o.open));
+            highs.push(q.ohlc.as_ref().map(|o| o.high));
+            lows.push(q.ohlc.as_ref().map(|o| o.low));
+            closes.push(q.ohlc.as_ref().map(|o| o.close));
+        }
+    });
+
+    let df = stage!(
+        "build_frame",
+        DataFrame::new(vec![
+            Series::new("symbol", &symbols),
+            Series::new("instrument_token", &instrument_tokens),
+            Series::new("timestamp", &timestamps),
+            Series::new("last_trade_time", &last_trade_times),
+            Series::new("last_price", &last_prices),
+            Series::new("last_quantity", &last_quantities),
+            Series::new("buy_quantity", &buy_quantities),
+            Series::new("sell_quantity", &sell_quantities),
+            Series::new("volume", &volumes),
+            Series::new("average_price", &average_prices),
+            Series::new("oi", &ois),
+            Series::new("oi_day_high", &oi_day_highs),
+            Series::new("oi_day_low", &oi_day_lows),
+            Series::new("net_change", &net_changes),
+            Series::new("lower_circuit_limit", &lower_circuit_limits),
+            Series::new("upper_circuit_limit", &upper_circuit_limits),
+            Series::new("open", &opens),
+            Series::new("high", &highs),
+            Series::new("low", &lows),
+            Series::new("close", &closes),
+        ])
+    )?;
+
+    Ok(df)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(instruments = quote.instruments.len()))
+)]
+pub fn quote_to_polars_df_from_series_v0(quote: Quotes) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut symbols = Vec::with_capacity(len);
+    let mut instrument_tokens = Vec::with_capacity(len);
+    let mut timestamps = Vec::with_capacity(len);
+    let mut last_trade_times = Vec::with_capacity(len);
+    let mut last_prices = Vec::with_capacity(len);
+    let mut last_quantities = Vec::with_capacity(len);
+    let mut buy_quantities = Vec::with_capacity(len);
+    let mut sell_quantities = Vec::with_capacity(len);
+    let mut volumes = Vec::with_capacity(len);
+    let mut average_prices = Vec::with_capacity(len);
+    let mut ois = Vec::with_capacity(len);
+    let mut oi_day_highs = Vec::with_capacity(len);
+    let mut oi_day_lows = Vec::with_capacity(len);
+    let mut net_changes = Vec::with_capacity(len);
+    let mut lower_circuit_limits = Vec::with_capacity(len);
+    let mut upper_circuit_limits = Vec::with_capacity(len);
+    let mut opens = Vec::with_capacity(len);
+    let mut highs = Vec::with_capacity(len);
+    let mut lows = Vec::with_capacity(len);
+    let mut closes = Vec::with_capacity(len);
+    let mut series_buf: Vec<Series> = Vec::with_capacity(20);
+
+    for _ in 0..20 {
+        series_buf.push(Series::new("symbol", vec![0u64; len]));
+    }
+
+    stage!("fill_columns", {
+        for (symbol, q) in quote.instruments {
+            symbols.push(symbol);
+            instrument_tokens.push(q.instrument_token);
+            timestamps.push(q.timestamp.clone());
+            last_trade_times.push(q.last_trade_time.clone());
+            last_prices.push(q.last_price);
+            last_quantities.push(q.last_quantity);
+            buy_quantities.push(q.buy_quantity);
+            sell_quantities.push(q.sell_quantity);
+            volumes.push(q.volume);
+            average_prices.push(q.average_price);
+            ois.push(q.oi);
+            oi_day_highs.push(q.oi_day_high);
+            oi_day_lows.push(q.oi_day_low);
+            net_changes.push(q.net_change);
+            lower_circuit_limits.push(q.lower_circuit_limit);
+            upper_circuit_limits.push(q.upper_circuit_limit);
+            opens.push(q.ohlc.as_ref().map(|o| o.open));
+            highs.push(q.ohlc.as_ref().map(|o| o.high));
+            lows.push(q.ohlc.as_ref().map(|o| o.low));
+            closes.push(q.ohlc.as_ref().map(|o| o.close));
+        }
+    });
+
+    assert_eq!(series_buf.len(), 20);
+
+    series_buf[0] = Series::new("symbol", &symbols);
+    series_buf[1] = Series::new("instrument_token", &instrument_tokens);
+    series_buf[2] = Series::new("timestamp", &timestamps);
+    series_buf[3] = Series::new("last_trade_time", &last_trade_times);
+    series_buf[4] = Series::new("last_price", &last_prices);
+    series_buf[5] = Series::new("last_quantity", &last_quantities);
+    series_buf[6] = Series::new("buy_quantity", &buy_quantities);
+    series_buf[7] = Series::new("sell_quantity", &sell_quantities);
+    series_buf[8] = Series::new("volume", &volumes);
+    series_buf[9] = Series::new("average_price", &average_prices);
+    series_buf[10] = Series::new("oi", &ois);
+    series_buf[11] = Series::new("oi_day_high", &oi_day_highs);
+    series_buf[12] = Series::new("oi_day_low", &oi_day_lows);
+    series_buf[13] = Series::new("net_change", &net_changes);
+    series_buf[14] = Series::new("lower_circuit_limit", &lower_circuit_limits);
+    series_buf[15] = Series::new("upper_circuit_limit", &upper_circuit_limits);
+    series_buf[16] = Series::new("open", &opens);
+    series_buf[17] = Series::new("high", &highs);
+    series_buf[18] = Series::new("low", &lows);
+    series_buf[19] = Series::new("close", &closes);
+
+    stage!("build_frame", DataFrame::new(series_buf))
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(instruments = quote.instruments.len()))
+)]
+pub fn quote_to_polars_df_from_series_v1(quote: Quotes) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut symbols = vec!["".to_string(); len];
+    let mut instrument_tokens = vec![0; len];
+    let mut timestamps = vec!["".to_string(); len];
+    let mut last_trade_times = vec!["".to_string(); len];
+    let mut last_prices = vec![0.0; len];
+    let mut last_quantities = vec![0; len];
+    let mut buy_quantities = vec![0; len];
+    let mut sell_quantities = vec![0; len];
+    let mut volumes = vec![0; len];
+    let mut average_prices = vec![0.0; len];
+    let mut ois = vec![0; len];
+    let mut oi_day_highs = vec![0; len];
+    let mut oi_day_lows = vec![0; len];
+    let mut net_changes = vec![0.0; len];
+    let mut lower_circuit_limits = vec![0.0; len];
+    let mut upper_circuit_limits = vec![0.0; len];
+    let mut opens = vec![None; len];
+    let mut highs = vec![None; len];
+    let mut lows = vec![None; len];
+    let mut closes = vec![None; len];
+
+    stage!(
+        "fill_columns",
+        quote
+            .instruments
+            .iter()
+            .enumerate()
+            .for_each(|(i, (symbol, q))| {
+                // Writing directly to vector elements to avoid push overhead
+                symbols[i] = symbol.clone();
+                instrument_tokens[i] = q.instrument_token;
+                timestamps[i] = q.timestamp.clone();
+                last_trade_times[i] = q.last_trade_time.clone();
+                last_prices[i] = q.last_price;
+                last_quantities[i] = q.last_quantity;
+                buy_quantities[i] = q.buy_quantity;
+                sell_quantities[i] = q.sell_quantity;
+                volumes[i] = q.volume;
+                average_prices[i] = q.average_price;
+                ois[i] = q.oi;
+                oi_day_highs[i] = q.oi_day_high;
+                oi_day_lows[i] = q.oi_day_low;
+                net_changes[i] = q.net_change;
+                lower_circuit_limits[i] = q.lower_circuit_limit;
+                upper_circuit_limits[i] = q.upper_circuit_limit;
+                opens[i] = q.ohlc.as_ref().map(|o| o.open);
+                highs[i] = q.ohlc.as_ref().map(|o| o.high);
+                lows[i] = q.ohlc.as_ref().map(|o| o.low);
+                closes[i] = q.ohlc.as_ref().map(|o| o.close);
+            })
+    );
+
+    stage!(
+        "build_frame",
+        DataFrame::new(vec![
+            Series::new("symbol", &symbols),
+            Series::new("instrument_token", &instrument_tokens),
+            Series::new("timestamp", &timestamps),
+            Series::new("last_trade_time", &last_trade_times),
+            Series::new("last_price", &last_prices),
+            Series::new("last_quantity", &last_quantities),
+            Series::new("buy_quantity", &buy_quantities),
+            Series::new("sell_quantity", &sell_quantities),
+            Series::new("volume", &volumes),
+            Series::new("average_price", &average_prices),
+            Series::new("oi", &ois),
+            Series::new("oi_day_high", &oi_day_highs),
+            Series::new("oi_day_low", &oi_day_lows),
+            Series::new("net_change", &net_changes),
+            Series::new("lower_circuit_limit", &lower_circuit_limits),
+            Series::new("upper_circuit_limit", &upper_circuit_limits),
+            Series::new("open", &opens),
+            Series::new("high", &highs),
+            Series::new("low", &lows),
+            Series::new("close", &closes),
+        ])
+    )
+}
+
+pub fn quote_to_polars_df_from_series_v2(quote: Quotes) -> Result<DataFrame, PolarsError> {
+    quote_to_polars_df_from_series_v2_with_strict(quote, true)
+}
+
+// With `strict` on, any value that doesn't match its column's dtype fails the
+// whole frame. With it off, Polars coerces what it can and turns the rest into
+// nulls, so the remaining rows survive.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(instruments = quote.instruments.len()))
+)]
+pub fn quote_to_polars_df_from_series_v2_with_strict(
+    quote: Quotes,
+    strict: bool,
+) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut series_buf: Vec<Series> = Vec::with_capacity(20);
+    let mut buf: Vec<Vec<AnyValue>> = vec![vec![AnyValue::Null; len]; 20];
+
+    assert_eq!(buf.len(), 20);
+
+    stage!(
+        "fill_columns",
+        quote
+            .instruments
+            .iter()
+            .enumerate()
+            .for_each(|(i, (symbol, q))| {
+                buf[0][i] = AnyValue::StringOwned(symbol.into());
+                buf[1][i] = q.instrument_token.into();
+                buf[2][i] = AnyValue::StringOwned(q.timestamp.clone().into());
+                buf[3][i] = AnyValue::StringOwned(q.last_trade_time.clone().into());
+                buf[4][i] = q.last_price.into();
+                buf[5][i] = q.last_quantity.into();
+                buf[6][i] = q.buy_quantity.into();
+                buf[7][i] = q.sell_quantity.into();
+                buf[8][i] = q.volume.into();
+                buf[9][i] = q.average_price.into();
+                buf[10][i] = q.oi.into();
+                buf[11][i] = q.oi_day_high.into();
+                buf[12][i] = q.oi_day_low.into();
+                buf[13][i] = q.net_change.into();
+                buf[14][i] = q.lower_circuit_limit.into();
+                buf[15][i] = q.upper_circuit_limit.into();
+                buf[16][i] = q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.open.into());
+                buf[17][i] = q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.high.into());
+                buf[18][i] = q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.low.into());
+                buf[19][i] = q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.close.into());
+            })
+    );
+    stage!("build_frame", {
+        series_buf.push(Series::from_any_values_and_dtype(
+            "symbol",
+            &buf[0],
+            &DataType::String,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "instrument_token",
+            &buf[1],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "timestamp",
+            &buf[2],
+            &DataType::String,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "last_trade_time",
+            &buf[3],
+            &DataType::String,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "last_price",
+            &buf[4],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "last_quantity",
+            &buf[5],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "buy_quantity",
+            &buf[6],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "sell_quantity",
+            &buf[7],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "volume",
+            &buf[8],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "average_price",
+            &buf[9],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "oi",
+            &buf[10],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "oi_day_high",
+            &buf[11],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "oi_day_low",
+            &buf[12],
+            &DataType::UInt64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "net_change",
+            &buf[13],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "lower_circuit_limit",
+            &buf[14],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "upper_circuit_limit",
+            &buf[15],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "open",
+            &buf[16],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "high",
+            &buf[17],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "low",
+            &buf[18],
+            &DataType::Float64,
+            strict,
+        )?);
+        series_buf.push(Series::from_any_values_and_dtype(
+            "close",
+            &buf[19],
+            &DataType::Float64,
+            strict,
+        )?);
+        DataFrame::new(series_buf)
+    })
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(instruments = quote.instruments.len()))
+)]
+pub fn quote_to_polars_df_from_series_v3(quote: Quotes) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut symbols = Vec::with_capacity(len);
+    let mut instrument_tokens = Vec::with_capacity(len);
+    let mut timestamps = Vec::with_capacity(len);
+    let mut last_trade_times = Vec::with_capacity(len);
+    let mut last_prices = Vec::with_capacity(len);
+    let mut last_quantities = Vec::with_capacity(len);
+    let mut buy_quantities = Vec::with_capacity(len);
+    let mut sell_quantities = Vec::with_capacity(len);
+    let mut volumes = Vec::with_capacity(len);
+    let mut average_prices = Vec::with_capacity(len);
+    let mut ois = Vec::with_capacity(len);
+    let mut oi_day_highs = Vec::with_capacity(len);
+    let mut oi_day_lows = Vec::with_capacity(len);
+    let mut net_changes = Vec::with_capacity(len);
+    let mut lower_circuit_limits = Vec::with_capacity(len);
+    let mut upper_circuit_limits = Vec::with_capacity(len);
+    let mut opens = Vec::with_capacity(len);
+    let mut highs = Vec::with_capacity(len);
+    let mut lows = Vec::with_capacity(len);
+    let mut closes = Vec::with_capacity(len);
+    let mut series_buf: Vec<Series> = Vec::with_capacity(20);
+
+    for _ in 0..20 {
+        series_buf.push(Series::new("symbol", vec![0u64; len]));
+    }
+
+    for _ in 0..len {
+        symbols.push("".to_string());
+        instrument_tokens.push(0);
+        timestamps.push("".to_string());
+        last_trade_times.push("".to_string());
+        last_prices.push(0.0);
+        last_quantities.push(0);
+        buy_quantities.push(0);
+        sell_quantities.push(0);
+        volumes.push(0);
+        average_prices.push(0.0);
+        ois.push(0);
+        oi_day_highs.push(0);
+        oi_day_lows.push(0);
+        net_changes.push(0.0);
+        lower_circuit_limits.push(0.0);
+        upper_circuit_limits.push(0.0);
+        opens.push(None);
+        highs.push(None);
+        lows.push(None);
+        closes.push(None);
+    }
+
+    stage!(
+        "fill_columns",
+        quote
+            .instruments
+            .iter()
+            .enumerate()
+            .for_each(|(i, (symbol, q))| {
+                symbols[i] = symbol.clone();
+                instrument_tokens[i] = q.instrument_token;
+                timestamps[i] = q.timestamp.clone();
+                last_trade_times[i] = q.last_trade_time.clone();
+                last_prices[i] = q.last_price;
+                last_quantities[i] = q.last_quantity;
+                buy_quantities[i] = q.buy_quantity;
+                sell_quantities[i] = q.sell_quantity;
+                volumes[i] = q.volume;
+                average_prices[i] = q.average_price;
+                ois[i] = q.oi;
+                oi_day_highs[i] = q.oi_day_high;
+                oi_day_lows[i] = q.oi_day_low;
+                net_changes[i] = q.net_change;
+                lower_circuit_limits[i] = q.lower_circuit_limit;
+                upper_circuit_limits[i] = q.upper_circuit_limit;
+                opens[i] = q.ohlc.as_ref().map(|o| o.open);
+                highs[i] = q.ohlc.as_ref().map(|o| o.high);
+                lows[i] = q.ohlc.as_ref().map(|o| o.low);
+                closes[i] = q.ohlc.as_ref().map(|o| o.close);
+            })
+    );
+
+    assert_eq!(series_buf.len(), 20);
+
+    series_buf[0] = Series::new("symbol", &symbols);
+    series_buf[1] = Series::new("instrument_token", &instrument_tokens);
+    series_buf[2] = Series::new("timestamp", &timestamps);
+    series_buf[3] = Series::new("last_trade_time", &last_trade_times);
+    series_buf[4] = Series::new("last_price", &last_prices);
+    series_buf[5] = Series::new("last_quantity", &last_quantities);
+    series_buf[6] = Series::new("buy_quantity", &buy_quantities);
+    series_buf[7] = Series::new("sell_quantity", &sell_quantities);
+    series_buf[8] = Series::new("volume", &volumes);
+    series_buf[9] = Series::new("average_price", &average_prices);
+    series_buf[10] = Series::new("oi", &ois);
+    series_buf[11] = Series::new("oi_day_high", &oi_day_highs);
+    series_buf[12] = Series::new("oi_day_low", &oi_day_lows);
+    series_buf[13] = Series::new("net_change", &net_changes);
+    series_buf[14] = Series::new("lower_circuit_limit", &lower_circuit_limits);
+    series_buf[15] = Series::new("upper_circuit_limit", &upper_circuit_limits);
+    series_buf[16] = Series::new("open", &opens);
+    series_buf[17] = Series::new("high", &highs);
+    series_buf[18] = Series::new("low", &lows);
+    series_buf[19] = Series::new("close", &closes);
+
+    stage!("build_frame", DataFrame::new(series_buf))
+}
+
+// Taken from an empty `quote_series` so the names, order and dtypes can't
+// drift from the frame it builds.
+pub fn quotes_schema() -> Schema {
+    quote_series(&Quotes::default())
+        .iter()
+        .map(|s| s.field().into_owned())
+        .collect()
+}
+
+pub fn quotes_arrow_schema() -> ArrowSchema {
+    quotes_schema().to_arrow(CompatLevel::newest())
+}
+
+pub fn quote_to_polars_df_from_json(
+    json: BufReader<File>,
+) -> Result<Option<DataFrame>, PolarsError> {
+    let schema = quotes_schema();
+
+    let df = JsonReader::new(json)
+        .with_json_format(JsonFormat::Json)
+        .infer_schema_len(Some(NonZeroUsize::new(100).unwrap()))
+        .with_schema_overwrite(&schema)
+        .finish()?;
+    Ok(Some(df))
+}
+
+pub fn write_df_to_ipc<P: AsRef<Path>>(df: &mut DataFrame, path: P) -> Result<(), PolarsError> {
+    let file = File::create(path)?;
+    IpcWriter::new(file).finish(df)
+}
+
+pub fn read_df_from_ipc<P: AsRef<Path>>(path: P) -> Result<DataFrame, PolarsError> {
+    let file = File::open(path)?;
+    IpcReader::new(file).finish()
+}
+
+pub fn write_df_to_csv<P: AsRef<Path>>(df: &mut DataFrame, path: P) -> Result<(), PolarsError> {
+    let file = File::create(path)?;
+    CsvWriter::new(file).finish(df)
+}
+
+pub fn quote_df_from_csv<P: AsRef<Path>>(path: P) -> Result<DataFrame, PolarsError> {
+    let schema = quotes_schema();
+    let df = CsvReadOptions::default()
+        .with_has_header(true)
+        .with_schema_overwrite(Some(Arc::new(schema.clone())))
+        .try_into_reader_with_file_path(Some(path.as_ref().to_path_buf()))?
+        .finish()?;
+    check_quotes_schema(&df)?;
+    df.select(schema.iter_names())
+}
+
+// Reads Kite's instruments master dump. Numeric ids are typed explicitly so
+// they join against the quote frame's `instrument_token`; an empty `expiry`
+// (equities, indices) reads as null.
+pub fn load_instruments_csv<P: AsRef<Path>>(path: P) -> Result<DataFrame, PolarsError> {
+    let schema = Schema::from_iter([
+        Field::new("instrument_token", DataType::UInt64),
+        Field::new("exchange_token", DataType::UInt64),
+        Field::new("tradingsymbol", DataType::String),
+        Field::new("name", DataType::String),
+        Field::new("last_price", DataType::Float64),
+        Field::new("expiry", DataType::Date),
+        Field::new("strike", DataType::Float64),
+        Field::new("tick_size", DataType::Float64),
+        Field::new("lot_size", DataType::UInt64),
+        Field::new("instrument_type", DataType::String),
+        Field::new("segment", DataType::String),
+        Field::new("exchange", DataType::String),
+    ]);
+    CsvReadOptions::default()
+        .with_has_header(true)
+        .with_schema_overwrite(Some(Arc::new(schema)))
+        .try_into_reader_with_file_path(Some(path.as_ref().to_path_buf()))?
+        .finish()
+}
+
+// Left-joins `tradingsymbol`, `name` and `expiry` from the instruments master
+// onto a quote frame by `instrument_token`. Quotes missing from the master are
+// kept with nulls, and the quote frame's row order is preserved.
+pub fn join_instrument_names(
+    quote_df: DataFrame,
+    instruments_df: &DataFrame,
+) -> Result<DataFrame, PolarsError> {
+    let names = instruments_df.select(["instrument_token", "tradingsymbol", "name", "expiry"])?;
+    quote_df.join(
+        &names,
+        ["instrument_token"],
+        ["instrument_token"],
+        JoinArgs::new(JoinType::Left),
+    )
+}
+
+// Files `quotes_df_from_dir_with_skipped` couldn't read, with the error.
+pub type SkippedFiles = Vec<(PathBuf, Box<dyn Error>)>;
+
+// Files are read in name order and each row is tagged with its file name in
+// `source_file`. With `strict` off, unreadable files are skipped with a
+// warning (through `tracing`, when enabled); with it on they're an error.
+pub fn quotes_df_from_dir<P: AsRef<Path>>(
+    dir: P,
+    glob: &str,
+    strict: bool,
+) -> Result<DataFrame, Box<dyn Error>> {
+    let (df, _skipped) = quotes_df_from_dir_with_skipped(dir, glob, strict)?;
+    #[cfg(feature = "tracing")]
+    for (path, e) in &_skipped {
+        tracing::warn!(path = %path.display(), error = %e, "skipping quotes file");
+    }
+    Ok(df)
+}
+
+// As `quotes_df_from_dir`, but also returns the skipped files in read order.
+pub fn quotes_df_from_dir_with_skipped<P: AsRef<Path>>(
+    dir: P,
+    glob: &str,
+    strict: bool,
+) -> Result<(DataFrame, SkippedFiles), Box<dyn Error>> {
+    let pattern = glob::Pattern::new(glob)?;
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let matches = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| pattern.matches(name));
+        if matches && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut series_buf = quote_series(&Quotes::default());
+    series_buf.push(Series::new_empty("source_file", &DataType::String));
+    let mut combined = DataFrame::new(series_buf)?;
+    let mut skipped = Vec::new();
+    for path in paths {
+        let source_file = path.file_name().unwrap_or_default().to_string_lossy();
+        let quotes: Quotes = match read_json_from_file(&path)
+            .and_then(|reader| Ok(serde_json::from_reader(reader)?))
+        {
+            Ok(quotes) => quotes,
+            Err(e) if strict => return Err(format!("{}: {e}", path.display()).into()),
+            Err(e) => {
+                skipped.push((path, e));
+                continue;
+            }
+        };
+        let mut series_buf = quote_series(&quotes);
+        series_buf.push(Series::new(
+            "source_file",
+            vec![source_file.as_ref(); quotes.instruments.len()],
+        ));
+        combined.vstack_mut(&DataFrame::new(series_buf)?)?;
+    }
+    combined.align_chunks();
+    Ok((combined, skipped))
+}
+
+// `captured_at` is the file's modification time in UTC, the same on every row.
+pub fn quotes_df_from_file_with_capture_time<P: AsRef<Path>>(
+    path: P,
+) -> Result<DataFrame, Box<dyn Error>> {
+    let modified = std::fs::metadata(&path)?.modified()?;
+    let captured_at = chrono::DateTime::<chrono::Utc>::from(modified).naive_utc();
+    let reader = read_json_from_file(&path)?;
+    let quotes: Quotes = serde_json::from_reader(reader)?;
+
+    let mut series_buf = quote_series(&quotes);
+    series_buf.push(Series::new(
+        "captured_at",
+        vec![captured_at; quotes.instruments.len()],
+    ));
+    Ok(DataFrame::new(series_buf)?)
+}
+
+// Parses the instruments map incrementally and writes every `batch`
+// instruments as their own row group, so at most one batch is ever held in
+// memory. Returns the number of rows written. Unlike a full parse, a symbol
+// repeated in the payload is written once per occurrence. On error the
+// partially written output file is removed.
+pub fn stream_quotes_file_to_parquet<I: AsRef<Path>, O: AsRef<Path>>(
+    input: I,
+    output: O,
+    batch: usize,
+) -> Result<usize, Box<dyn Error>> {
+    if batch == 0 {
+        return Err("batch size must be non-zero".into());
+    }
+    let reader = read_json_from_file(input)?;
+    let output = output.as_ref();
+    let file = File::create(output)?;
+    let written = write_quote_batches(reader, file, batch);
+    if written.is_err() {
+        let _ = std::fs::remove_file(output);
+    }
+    written
+}
+
+fn write_quote_batches(
+    reader: BufReader<File>,
+    file: File,
+    batch: usize,
+) -> Result<usize, Box<dyn Error>> {
+    let mut writer = ParquetWriter::new(file).batched(&quotes_schema())?;
+
+    let failed = Cell::new(None);
+    let visitor = QuoteBatches {
+        batch,
+        failed: &failed,
+        write: |quotes: &Quotes| writer.write_batch(&DataFrame::new(quote_series(quotes))?),
+    };
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let written = deserializer
+        .deserialize_map(visitor)
+        .and_then(|written| deserializer.end().map(|_| written));
+    let written = match written {
+        Ok(written) => written,
+        Err(e) => return Err(failed.take().map_or_else(|| e.into(), Into::into)),
+    };
+    writer.finish()?;
+    Ok(written)
+}
+
+struct QuoteBatches<'a, F> {
+    batch: usize,
+    failed: &'a Cell<Option<PolarsError>>,
+    write: F,
+}
+
+impl<'de, F> Visitor<'de> for QuoteBatches<'_, F>
+where
+    F: FnMut(&Quotes) -> Result<(), PolarsError>,
+{
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of instruments")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut pending = Quotes::default();
+        let mut written = 0;
+        let mut flush = |pending: &mut Quotes| -> Result<(), A::Error> {
+            (self.write)(pending).map_err(|e| {
+                let message = e.to_string();
+                self.failed.set(Some(e));
+                de::Error::custom(message)
+            })?;
+            written += pending.instruments.len();
+            pending.instruments.clear();
+            Ok(())
+        };
+        while let Some((symbol, data)) = map.next_entry::<String, QuotesData>()? {
+            if pending.instruments.contains_key(&symbol) {
+                flush(&mut pending)?;
+            }
+            pending.instruments.insert(symbol, data);
+            if pending.instruments.len() == self.batch {
+                flush(&mut pending)?;
+            }
+        }
+        if !pending.instruments.is_empty() {
+            flush(&mut pending)?;
+        }
+        Ok(written)
+    }
+}
+
+pub trait QuoteSink {
+    fn write(&mut self, df: &DataFrame) -> Result<(), PolarsError>;
+}
+
+// Appends rows to the wrapped writer; the header is only written once, so
+// several frames make one valid CSV as long as their columns match.
+pub struct CsvSink<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        CsvSink {
+            writer,
+            header_written: false,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> QuoteSink for CsvSink<W> {
+    fn write(&mut self, df: &DataFrame) -> Result<(), PolarsError> {
+        CsvWriter::new(&mut self.writer)
+            .include_header(!self.header_written)
+            .finish(&mut df.clone())?;
+        self.header_written = true;
+        Ok(())
+    }
+}
+
+// Each write emits a complete Parquet file, so a second write is rejected
+// rather than appending another file to the same writer.
+pub struct ParquetSink<W: Write> {
+    writer: W,
+    written: bool,
+}
+
+impl<W: Write> ParquetSink<W> {
+    pub fn new(writer: W) -> Self {
+        ParquetSink {
+            writer,
+            written: false,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> QuoteSink for ParquetSink<W> {
+    fn write(&mut self, df: &DataFrame) -> Result<(), PolarsError> {
+        if self.written {
+            return Err(PolarsError::InvalidOperation(
+                "ParquetSink already holds a complete file; use a new sink per frame".into(),
+            ));
+        }
+        ParquetWriter::new(&mut self.writer).finish(&mut df.clone())?;
+        self.written = true;
+        Ok(())
+    }
+}
+
+// Each write emits a complete IPC file, so a second write is rejected
+// rather than appending another file to the same writer.
+pub struct IpcSink<W: Write> {
+    writer: W,
+    written: bool,
+}
+
+impl<W: Write> IpcSink<W> {
+    pub fn new(writer: W) -> Self {
+        IpcSink {
+            writer,
+            written: false,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> QuoteSink for IpcSink<W> {
+    fn write(&mut self, df: &DataFrame) -> Result<(), PolarsError> {
+        if self.written {
+            return Err(PolarsError::InvalidOperation(
+                "IpcSink already holds a complete file; use a new sink per frame".into(),
+            ));
+        }
+        IpcWriter::new(&mut self.writer).finish(&mut df.clone())?;
+        self.written = true;
+        Ok(())
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(instruments = quote.instruments.len()))
+)]
+pub fn quote_to_polars_df_from_rows_cols(quote: Quotes) -> Result<DataFrame, PolarsError> {
+    let mut dfbuf: Vec<Row> = Vec::with_capacity(quote.instruments.len());
+    let mut buf: Vec<AnyValue> = Vec::with_capacity(20);
+
+    let schema = quotes_schema();
+
+    stage!("fill_columns", {
+        for (symbol, q) in quote.instruments {
+            buf.clear();
+            buf.push(AnyValue::StringOwned(symbol.into()));
+            buf.push(q.instrument_token.into());
+            buf.push(AnyValue::StringOwned(q.timestamp.into()));
+            buf.push(AnyValue::StringOwned(q.last_trade_time.into()));
+            buf.push(q.last_price.into());
+            buf.push(q.last_quantity.into());
+            buf.push(q.buy_quantity.into());
+            buf.push(q.sell_quantity.into());
+            buf.push(q.volume.into());
+            buf.push(q.average_price.into());
+            buf.push(q.oi.into());
+            buf.push(q.oi_day_high.into());
+            buf.push(q.oi_day_low.into());
+            buf.push(q.net_change.into());
+            buf.push(q.lower_circuit_limit.into());
+            buf.push(q.upper_circuit_limit.into());
+            buf.push(q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.open.into()));
+            buf.push(q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.high.into()));
+            buf.push(q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.low.into()));
+            buf.push(q.ohlc.as_ref().map_or(AnyValue::Null, |o| o.close.into()));
+            dfbuf.push(Row::new(buf.clone()));
+        }
+    });
+
+    let df = stage!(
+        "build_frame",
+        DataFrame::from_rows_and_schema(&dfbuf, &schema)
+    )?;
+    Ok(df)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnNames {
+    pub symbol: String,
+    pub instrument_token: String,
+    pub timestamp: String,
+    pub last_trade_time: String,
+    pub last_price: String,
+    pub last_quantity: String,
+    pub buy_quantity: String,
+    pub sell_quantity: String,
+    pub volume: String,
+    pub average_price: String,
+    pub oi: String,
+    pub oi_day_high: String,
+    pub oi_day_low: String,
+    pub net_change: String,
+    pub lower_circuit_limit: String,
+    pub upper_circuit_limit: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+}
+
+impl Default for ColumnNames {
+    fn default() -> Self {
+        ColumnNames {
+            symbol: "symbol".to_owned(),
+            instrument_token: "instrument_token".to_owned(),
+            timestamp: "timestamp".to_owned(),
+            last_trade_time: "last_trade_time".to_owned(),
+            last_price: "last_price".to_owned(),
+            last_quantity: "last_quantity".to_owned(),
+            buy_quantity: "buy_quantity".to_owned(),
+            sell_quantity: "sell_quantity".to_owned(),
+            volume: "volume".to_owned(),
+            average_price: "average_price".to_owned(),
+            oi: "oi".to_owned(),
+            oi_day_high: "oi_day_high".to_owned(),
+            oi_day_low: "oi_day_low".to_owned(),
+            net_change: "net_change".to_owned(),
+            lower_circuit_limit: "lower_circuit_limit".to_owned(),
+            upper_circuit_limit: "upper_circuit_limit".to_owned(),
+            open: "open".to_owned(),
+            high: "high".to_owned(),
+            low: "low".to_owned(),
+            close: "close".to_owned(),
+        }
+    }
+}
+
+impl ColumnNames {
+    fn in_schema_order(&self) -> [&str; 20] {
+        [
+            &self.symbol,
+            &self.instrument_token,
+            &self.timestamp,
+            &self.last_trade_time,
+            &self.last_price,
+            &self.last_quantity,
+            &self.buy_quantity,
+            &self.sell_quantity,
+            &self.volume,
+            &self.average_price,
+            &self.oi,
+            &self.oi_day_high,
+            &self.oi_day_low,
+            &self.net_change,
+            &self.lower_circuit_limit,
+            &self.upper_circuit_limit,
+            &self.open,
+            &self.high,
+            &self.low,
+            &self.close,
+        ]
+    }
+}
+
+impl QuotesColumns {
+    pub fn into_dataframe(self) -> Result<DataFrame, PolarsError> {
+        DataFrame::new(self.into_series())
+    }
+
+    fn into_series(self) -> Vec<Series> {
+        vec![
+            Series::new("symbol", self.symbols),
+            Series::new("instrument_token", self.instrument_tokens),
+            Series::new("timestamp", self.timestamps),
+            Series::new("last_trade_time", self.last_trade_times),
+            Series::new("last_price", self.last_prices),
+            Series::new("last_quantity", self.last_quantities),
+            Series::new("buy_quantity", self.buy_quantities),
+            Series::new("sell_quantity", self.sell_quantities),
+            Series::new("volume", self.volumes),
+            Series::new("average_price", self.average_prices),
+            Series::new("oi", self.ois),
+            Series::new("oi_day_high", self.oi_day_highs),
+            Series::new("oi_day_low", self.oi_day_lows),
+            Series::new("net_change", self.net_changes),
+            Series::new("lower_circuit_limit", self.lower_circuit_limits),
+            Series::new("upper_circuit_limit", self.upper_circuit_limits),
+            Series::new("open", self.opens),
+            Series::new("high", self.highs),
+            Series::new("low", self.lows),
+            Series::new("close", self.closes),
+        ]
+    }
+}
+
+type ColumnBuilder = fn(&Quotes, &str) -> Series;
+
+// The standard frame's columns in order, each with how to build it from the
+// instruments. `quote_series`, `quotes_schema` and `Quotes::column_series`
+// all read this table.
+const QUOTE_COLUMNS: [(&str, ColumnBuilder); 20] = [
+    ("symbol", |q, name| {
+        Series::new(
+            name,
+            q.instruments.keys().map(String::as_str).collect::<Vec<_>>(),
+        )
+    }),
+    ("instrument_token", |q, name| {
+        Series::new(name, q.col(|d| d.instrument_token))
+    }),
+    ("timestamp", |q, name| {
+        Series::new(name, q.col(|d| d.timestamp.as_str()))
+    }),
+    ("last_trade_time", |q, name| {
+        Series::new(name, q.col(|d| d.last_trade_time.as_str()))
+    }),
+    ("last_price", |q, name| {
+        Series::new(name, q.col(|d| d.last_price))
+    }),
+    ("last_quantity", |q, name| {
+        Series::new(name, q.col(|d| d.last_quantity))
+    }),
+    ("buy_quantity", |q, name| {
+        Series::new(name, q.col(|d| d.buy_quantity))
+    }),
+    ("sell_quantity", |q, name| {
+        Series::new(name, q.col(|d| d.sell_quantity))
+    }),
+    ("volume", |q, name| Series::new(name, q.col(|d| d.volume))),
+    ("average_price", |q, name| {
+        Series::new(name, q.col(|d| d.average_price))
+    }),
+    ("oi", |q, name| Series::new(name, q.col(|d| d.oi))),
+    ("oi_day_high", |q, name| {
+        Series::new(name, q.col(|d| d.oi_day_high))
+    }),
+    ("oi_day_low", |q, name| {
+        Series::new(name, q.col(|d| d.oi_day_low))
+    }),
+    ("net_change", |q, name| {
+        Series::new(name, q.col(|d| d.net_change))
+    }),
+    ("lower_circuit_limit", |q, name| {
+        Series::new(name, q.col(|d| d.lower_circuit_limit))
+    }),
+    ("upper_circuit_limit", |q, name| {
+        Series::new(name, q.col(|d| d.upper_circuit_limit))
+    }),
+    ("open", |q, name| {
+        Series::new(name, q.col(|d| d.ohlc.as_ref().map(|o| o.open)))
+    }),
+    ("high", |q, name| {
+        Series::new(name, q.col(|d| d.ohlc.as_ref().map(|o| o.high)))
+    }),
+    ("low", |q, name| {
+        Series::new(name, q.col(|d| d.ohlc.as_ref().map(|o| o.low)))
+    }),
+    ("close", |q, name| {
+        Series::new(name, q.col(|d| d.ohlc.as_ref().map(|o| o.close)))
+    }),
+];
+
+fn quote_series(quote: &Quotes) -> Vec<Series> {
+    QUOTE_COLUMNS
+        .iter()
+        .map(|(name, build)| build(quote, name))
+        .collect()
+}
+
+pub fn quote_to_polars_df_named(
+    quote: &Quotes,
+    names: &ColumnNames,
+) -> Result<DataFrame, PolarsError> {
+    let mut series_buf = quote_series(quote);
+    for (series, name) in series_buf.iter_mut().zip(names.in_schema_order()) {
+        series.rename(name);
+    }
+    DataFrame::new(series_buf)
+}
+
+// Kite's `ohlc.close` is the previous trading session's close, not today's,
+// so it is emitted as `prev_close`. With `keep_close` the original `close`
+// column is kept alongside it for code that still reads that name.
+pub fn quote_to_polars_df_prev_close_named(
+    quote: &Quotes,
+    keep_close: bool,
+) -> Result<DataFrame, PolarsError> {
+    let names = ColumnNames {
+        close: "prev_close".to_owned(),
+        ..ColumnNames::default()
+    };
+    let mut df = quote_to_polars_df_named(quote, &names)?;
+    if keep_close {
+        let close = df.column("prev_close")?.clone().with_name("close");
+        df.insert_column(df.width() - 1, close)?;
+    }
+    Ok(df)
+}
+
+impl Quotes {
+    // The (name, dtype) pairs of the standard frame, in column order.
+    pub fn column_dtypes() -> Vec<(String, DataType)> {
+        quotes_schema()
+            .iter()
+            .map(|(name, dtype)| (name.to_string(), dtype.clone()))
+            .collect()
+    }
+
+    pub fn convert_and_write(&self, sink: &mut impl QuoteSink) -> Result<(), PolarsError> {
+        sink.write(&DataFrame::new(quote_series(self))?)
+    }
+
+    // Builds a single column of the standard frame, in the same row order.
+    pub fn column_series(&self, name: &str) -> Result<Series, PolarsError> {
+        QUOTE_COLUMNS
+            .iter()
+            .find(|(column, _)| *column == name)
+            .map(|(column, build)| build(self, column))
+            .ok_or_else(|| {
+                PolarsError::ColumnNotFound(format!("{:?} is not a quotes column", name).into())
+            })
+    }
+}
+
+// A change too large for an i64 (possible only with OI near the u64 limits)
+// is an error naming the symbol rather than a wrapped value.
+pub fn quote_to_polars_df_with_oi_delta(
+    current: &Quotes,
+    previous: &Quotes,
+) -> Result<DataFrame, PolarsError> {
+    let previous_oi: HashMap<u64, u64> = previous
+        .instruments
+        .values()
+        .map(|q| (q.instrument_token, q.oi))
+        .collect();
+    let oi_changes = current
+        .instruments
+        .iter()
+        .map(|(symbol, q)| {
+            previous_oi
+                .get(&q.instrument_token)
+                .map(|&prev| {
+                    let change = i128::from(q.oi) - i128::from(prev);
+                    i64::try_from(change).map_err(|_| {
+                        PolarsError::ComputeError(
+                            format!("{symbol}: oi_change {change} does not fit in i64").into(),
+                        )
+                    })
+                })
+                .transpose()
+        })
+        .collect::<Result<Vec<Option<i64>>, PolarsError>>()?;
+
+    let mut series_buf = quote_series(current);
+    series_buf.push(Series::new("oi_change", &oi_changes));
+    DataFrame::new(series_buf)
+}
+
+// `tick_direction` is null with no previous price or when either price is
+// NaN, since there's no direction to report.
+pub fn quote_to_polars_df_with_price_tick(
+    current: &Quotes,
+    previous: &Quotes,
+) -> Result<DataFrame, PolarsError> {
+    let previous_prices: HashMap<u64, f64> = previous
+        .instruments
+        .values()
+        .map(|q| (q.instrument_token, q.last_price))
+        .collect();
+    let directions: Vec<Option<&str>> = current
+        .instruments
+        .values()
+        .map(|q| {
+            let prev = previous_prices.get(&q.instrument_token)?;
+            match q.last_price.partial_cmp(prev)? {
+                Ordering::Greater => Some("up"),
+                Ordering::Less => Some("down"),
+                Ordering::Equal => Some("unchanged"),
+            }
+        })
+        .collect();
+
+    let mut series_buf = quote_series(current);
+    series_buf.push(Series::new("tick_direction", &directions));
+    DataFrame::new(series_buf)
+}
+
+// `timestamp_parsed` in microseconds, Polars' own default unit.
+pub fn quote_to_polars_df_dual_timestamp(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    quote_to_polars_df_dual_timestamp_with_unit(quote, TimeUnit::Microseconds)
+}
+
+// Kite timestamps have whole-second resolution, so every unit holds the same
+// instants; pick the one the frame is joined against.
+pub fn quote_to_polars_df_dual_timestamp_with_unit(
+    quote: &Quotes,
+    unit: TimeUnit,
+) -> Result<DataFrame, PolarsError> {
+    let mut series_buf = quote_series(quote);
+    series_buf.push(parsed_timestamp_series(quote, unit)?);
+    DataFrame::new(series_buf)
+}
+
+fn parsed_timestamp_series(quote: &Quotes, unit: TimeUnit) -> Result<Series, PolarsError> {
+    let timestamps_parsed: Vec<Option<NaiveDateTime>> = quote
+        .instruments
+        .values()
+        .map(QuotesData::parsed_timestamp)
+        .collect();
+    Series::new("timestamp_parsed", &timestamps_parsed).cast(&DataType::Datetime(unit, None))
+}
+
+// Why an instrument can't go into a frame as-is, or None if it can. Kite's
+// `close` is the previous session's, so only `open` must lie within today's
+// range; an all-zero OHLC is an instrument that hasn't traded and passes, as
+// does a missing one.
+fn rejection_reason(q: &QuotesData) -> Option<&'static str> {
+    let ohlc = q.ohlc.clone().unwrap_or_default();
+    let prices = [
+        q.last_price,
+        q.average_price,
+        ohlc.open,
+        ohlc.high,
+        ohlc.low,
+        ohlc.close,
+    ];
+    let depth_prices = q.depth.buy.iter().chain(&q.depth.sell).map(|d| d.price);
+    if !prices.into_iter().chain(depth_prices).all(f64::is_finite) {
+        return Some("non-finite price");
+    }
+    let untraded = prices[2..].iter().all(|&p| p == 0.0);
+    let consistent = ohlc.low <= ohlc.open && ohlc.open <= ohlc.high;
+    if !(untraded || consistent) {
+        return Some("ohlc out of range");
+    }
+    if !q.depth.is_well_ordered() {
+        return Some("depth not well ordered");
+    }
+    None
+}
+
+// Builds the frame from the instruments that pass validation and reports the
+// rest as (symbol, reason) pairs sorted by symbol.
+pub fn quote_to_polars_df_lenient(quote: &Quotes) -> (DataFrame, Vec<(String, String)>) {
+    let mut accepted = Quotes::default();
+    let mut rejected = Vec::new();
+    for (symbol, q) in &quote.instruments {
+        match rejection_reason(q) {
+            None => {
+                accepted.instruments.insert(symbol.clone(), q.clone());
+            }
+            Some(reason) => rejected.push((symbol.clone(), reason.to_owned())),
+        }
+    }
+    rejected.sort();
+    let df = QuotesColumns::from_quotes(&accepted)
+        .into_dataframe()
+        .expect("base columns share one length");
+    (df, rejected)
+}
+
+// `dominant_side` is "buy", "sell" or null (tie or empty book), Categorical
+// like `exchange`.
+pub fn quote_to_polars_df_with_dominant_side(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let sides: Vec<Option<&str>> = quote
+        .instruments
+        .values()
+        .map(|q| q.dominant_side().map(|side| side.as_str()))
+        .collect();
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(
+        Series::new("dominant_side", &sides)
+            .cast(&DataType::Categorical(None, CategoricalOrdering::default()))?,
+    );
+    DataFrame::new(series_buf)
+}
+
+// Keys without a `:` get a null exchange and the whole key as tradingsymbol.
+// `exchange` is Categorical since a handful of values repeat on every row.
+pub fn quote_to_polars_df_with_exchange(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut exchanges = Vec::with_capacity(len);
+    let mut tradingsymbols = Vec::with_capacity(len);
+
+    for symbol in quote.instruments.keys() {
+        match symbol.split_once(':') {
+            Some((exchange, tradingsymbol)) => {
+                exchanges.push(Some(exchange));
+                tradingsymbols.push(tradingsymbol);
+            }
+            None => {
+                exchanges.push(None);
+                tradingsymbols.push(symbol.as_str());
+            }
+        }
+    }
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(
+        Series::new("exchange", &exchanges)
+            .cast(&DataType::Categorical(None, CategoricalOrdering::default()))?,
+    );
+    series_buf.push(Series::new("tradingsymbol", &tradingsymbols));
+    DataFrame::new(series_buf)
+}
+
+// Every UInt64 column (token, quantities, volume, OI) as Int64 for engines
+// without unsigned 64-bit support. Real values always fit in an i64; one that
+// doesn't is an error rather than a silent null.
+pub fn quote_to_polars_df_signed_tokens(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let series_buf = quote_series(quote)
+        .into_iter()
+        .map(signed_if_unsigned)
+        .collect::<Result<Vec<_>, _>>()?;
+    DataFrame::new(series_buf)
+}
+
+fn signed_if_unsigned(s: Series) -> Result<Series, PolarsError> {
+    match s.dtype() {
+        DataType::UInt64 => s.strict_cast(&DataType::Int64),
+        DataType::UInt32 => s.strict_cast(&DataType::Int32),
+        _ => Ok(s),
+    }
+}
+
+// With `float_quantities`, `volume` and the three quantity columns are Float64
+// so ratios over them need no cast; otherwise this is the standard UInt64
+// frame. Values above 2^53 lose precision in the float form.
+pub fn quote_to_polars_df_float_volume(
+    quote: &Quotes,
+    float_quantities: bool,
+) -> Result<DataFrame, PolarsError> {
+    const QUANTITY_COLUMNS: [&str; 4] =
+        ["last_quantity", "buy_quantity", "sell_quantity", "volume"];
+    let series_buf = quote_series(quote)
+        .into_iter()
+        .map(|s| {
+            if float_quantities && QUANTITY_COLUMNS.contains(&s.name()) {
+                s.cast(&DataType::Float64)
+            } else {
+                Ok(s)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    DataFrame::new(series_buf)
+}
+
+// Kite's `close` is the previous session's, so `gap` is today's open against
+// it; null when there's no prior close. Both are null without an OHLC block.
+pub fn quote_to_polars_df_with_ranges(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut day_ranges = Vec::with_capacity(len);
+    let mut gaps = Vec::with_capacity(len);
+
+    for q in quote.instruments.values() {
+        let ohlc = q.ohlc.as_ref();
+        day_ranges.push(ohlc.map(|o| o.high - o.low));
+        gaps.push(ohlc.filter(|o| o.close != 0.0).map(|o| o.open - o.close));
+    }
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(Series::new("day_range", &day_ranges));
+    series_buf.push(Series::new("gap", &gaps));
+    DataFrame::new(series_buf)
+}
+
+// `range_position` is where `last_price` sits in the day's range: 0 at the
+// low, 1 at the high. Null when high == low, including untraded instruments,
+// and without an OHLC block.
+// Not clamped, so a price outside the reported range shows up as < 0 or > 1.
+pub fn quote_to_polars_df_with_range_position(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let positions: Vec<Option<f64>> = quote
+        .instruments
+        .values()
+        .map(|q| {
+            let ohlc = q.ohlc.as_ref()?;
+            let range = ohlc.high - ohlc.low;
+            (range != 0.0).then(|| (q.last_price - ohlc.low) / range)
+        })
+        .collect();
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(Series::new("range_position", &positions));
+    DataFrame::new(series_buf)
+}
+
+pub fn quote_to_polars_df_with_tradable(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let tradables: Vec<Option<bool>> = quote.instruments.values().map(|q| q.tradable).collect();
+    let modes: Vec<Option<&str>> = quote
+        .instruments
+        .values()
+        .map(|q| q.mode.as_deref())
+        .collect();
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(Series::new("tradable", &tradables));
+    series_buf.push(Series::new("mode", &modes));
+    DataFrame::new(series_buf)
+}
+
+// The standard frame without its string columns (`symbol`, `timestamp`,
+// `last_trade_time`), so no per-row string is allocated. `instrument_token`
+// identifies the rows instead.
+pub fn quote_to_polars_df_numeric_only(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let columns = quotes_schema()
+        .iter()
+        .filter(|(_, dtype)| !dtype.is_string())
+        .map(|(name, _)| quote.column_series(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    DataFrame::new(columns)
+}
+
+// `row_hash` is 64-bit FNV-1a over the symbol and the 19 other standard
+// columns (token, both timestamps, prices, quantities, OI, circuit limits and
+// OHLC) in schema order. Strings are length-prefixed and numbers hashed as
+// little-endian bytes (floats by bit pattern), so the value is stable across
+// runs, platforms and toolchains. Depth, `tradable` and `mode` don't feed it.
+pub fn quote_to_polars_df_with_rowhash(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let hashes: Vec<u64> = quote
+        .instruments
+        .iter()
+        .map(|(symbol, q)| row_hash(symbol, q))
+        .collect();
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(Series::new("row_hash", &hashes));
+    DataFrame::new(series_buf)
+}
+
+fn row_hash(symbol: &str, q: &QuotesData) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(PRIME);
+        }
+    };
+    for text in [symbol, &q.timestamp, &q.last_trade_time] {
+        feed(&(text.len() as u64).to_le_bytes());
+        feed(text.as_bytes());
+    }
+    for value in [
+        q.instrument_token,
+        q.last_quantity,
+        q.buy_quantity,
+        q.sell_quantity,
+        q.volume,
+        q.oi,
+        q.oi_day_high,
+        q.oi_day_low,
+    ] {
+        feed(&value.to_le_bytes());
+    }
+    for value in [
+        q.last_price,
+        q.average_price,
+        q.net_change,
+        q.lower_circuit_limit,
+        q.upper_circuit_limit,
+    ] {
+        feed(&value.to_bits().to_le_bytes());
+    }
+    // A presence tag keeps a missing OHLC block distinct from an all-NaN one.
+    match &q.ohlc {
+        Some(o) => {
+            feed(&[1]);
+            for value in [o.open, o.high, o.low, o.close] {
+                feed(&value.to_bits().to_le_bytes());
+            }
+        }
+        None => feed(&[0]),
+    }
+    hash
+}
+
+// A zero limit means the instrument has no circuit band, so it never flags.
+pub fn quote_to_polars_df_with_circuit_flags(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut at_upper_circuits = Vec::with_capacity(len);
+    let mut at_lower_circuits = Vec::with_capacity(len);
+
+    for q in quote.instruments.values() {
+        at_upper_circuits
+            .push(q.upper_circuit_limit != 0.0 && q.last_price >= q.upper_circuit_limit);
+        at_lower_circuits
+            .push(q.lower_circuit_limit != 0.0 && q.last_price <= q.lower_circuit_limit);
+    }
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(Series::new("at_upper_circuit", &at_upper_circuits));
+    series_buf.push(Series::new("at_lower_circuit", &at_lower_circuits));
+    DataFrame::new(series_buf)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivedColumn {
+    ChangePct,
+    Spread,
+    MidPrice,
+    MicroPrice,
+    DepthImbalance,
+}
+
+impl DerivedColumn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DerivedColumn::ChangePct => "change_pct",
+            DerivedColumn::Spread => "spread",
+            DerivedColumn::MidPrice => "mid_price",
+            DerivedColumn::MicroPrice => "micro_price",
+            DerivedColumn::DepthImbalance => "depth_imbalance",
+        }
+    }
+
+    // None when the inputs can't produce a meaningful value: a zero or missing close,
+    // an empty or zero-priced side of the book, or no quantity on either side.
+    fn compute(&self, q: &QuotesData) -> Option<f64> {
+        match self {
+            DerivedColumn::ChangePct => {
+                let close = q.ohlc.as_ref()?.close;
+                (close != 0.0).then(|| (q.last_price - close) / close * 100.0)
+            }
+            DerivedColumn::Spread => q.best_spread(),
+            DerivedColumn::MidPrice => q.best_bid_ask().map(|(bid, ask)| (bid + ask) / 2.0),
+            // Each side's price weighted by the size resting on the other.
+            DerivedColumn::MicroPrice => {
+                let (bid, ask) = q.best_bid_ask()?;
+                let bid_qty = q.depth.buy[0].quantity as f64;
+                let ask_qty = q.depth.sell[0].quantity as f64;
+                let total = bid_qty + ask_qty;
+                (total != 0.0).then(|| (bid * ask_qty + ask * bid_qty) / total)
+            }
+            DerivedColumn::DepthImbalance => {
+                let total = q.buy_quantity as f64 + q.sell_quantity as f64;
+                (total != 0.0).then(|| (q.buy_quantity as f64 - q.sell_quantity as f64) / total)
+            }
+        }
+    }
+}
+
+pub fn quote_to_polars_df_with_microprice(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let mut df = DataFrame::new(quote_series(quote))?;
+    append_derived_columns(
+        &mut df,
+        quote,
+        &[DerivedColumn::MidPrice, DerivedColumn::MicroPrice],
+    )?;
+    Ok(df)
+}
+
+// `df` must have been built from the same, unmodified `quote` so its rows
+// follow the map's iteration order.
+pub fn append_derived_columns(
+    df: &mut DataFrame,
+    quote: &Quotes,
+    which: &[DerivedColumn],
+) -> Result<(), PolarsError> {
+    if df.height() != quote.instruments.len() {
+        return Err(PolarsError::ShapeMismatch(
+            format!(
+                "frame has {} rows but quotes have {} instruments",
+                df.height(),
+                quote.instruments.len()
+            )
+            .into(),
+        ));
+    }
+    for column in which {
+        let values: Vec<Option<f64>> = quote
+            .instruments
+            .values()
+            .map(|q| column.compute(q))
+            .collect();
+        df.with_column(Series::new(column.name(), &values))?;
+    }
+    Ok(())
+}
+
+// Remembers the last converted frame and hands out clones of it while the
+// input stays the same. Keys are either an xxh3 hash of the quotes' content
+// or a version token supplied by the caller, whichever method is used.
+#[cfg(feature = "cache")]
+#[derive(Debug, Default, Clone)]
+pub struct CachedConverter {
+    cached: Option<(CacheKey, DataFrame)>,
+}
+
+#[cfg(feature = "cache")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheKey {
+    Content(u64),
+    Version(u64),
+}
+
+#[cfg(feature = "cache")]
+struct HashWriter(xxhash_rust::xxh3::Xxh3);
+
+#[cfg(feature = "cache")]
+impl Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cache")]
+impl CachedConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Symbols are hashed in sorted order so equal maps hash equally
+    // regardless of their iteration order.
+    pub fn content_hash(quote: &Quotes) -> u64 {
+        let mut symbols: Vec<&String> = quote.instruments.keys().collect();
+        symbols.sort_unstable();
+        let mut hasher = HashWriter(xxhash_rust::xxh3::Xxh3::new());
+        for symbol in symbols {
+            hasher.0.update(symbol.as_bytes());
+            hasher.0.update(&[0]);
+            serde_json::to_writer(&mut hasher, &quote.instruments[symbol])
+                .expect("QuotesData serializes to JSON");
+            hasher.0.update(&[0]);
+        }
+        hasher.0.digest()
+    }
+
+    pub fn convert(&mut self, quote: &Quotes) -> Result<DataFrame, PolarsError> {
+        self.convert_keyed(CacheKey::Content(Self::content_hash(quote)), quote)
+    }
+
+    // Skips hashing entirely; the caller promises that an unchanged `version`
+    // means unchanged quotes.
+    pub fn convert_versioned(
+        &mut self,
+        version: u64,
+        quote: &Quotes,
+    ) -> Result<DataFrame, PolarsError> {
+        self.convert_keyed(CacheKey::Version(version), quote)
+    }
+
+    pub fn clear(&mut self) {
+        self.cached = None;
+    }
+
+    fn convert_keyed(&mut self, key: CacheKey, quote: &Quotes) -> Result<DataFrame, PolarsError> {
+        if let Some((cached_key, df)) = &self.cached {
+            if *cached_key == key {
+                return Ok(df.clone());
+            }
+        }
+        let df = DataFrame::new(quote_series(quote))?;
+        self.cached = Some((key, df.clone()));
+        Ok(df)
+    }
+}
+
+impl QuotesDiff {
+    // Prices are null for a symbol the diff didn't compute, e.g. one a caller
+    // pushed onto `changed` afterwards.
+    pub fn to_dataframe(&self) -> Result<DataFrame, PolarsError> {
+        let len = self.added.len() + self.removed.len() + self.changed.len();
+        let mut symbols = Vec::with_capacity(len);
+        let mut changes = Vec::with_capacity(len);
+        let mut old_last_prices = Vec::with_capacity(len);
+        let mut new_last_prices = Vec::with_capacity(len);
+
+        for (change, list) in [
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("changed", &self.changed),
+        ] {
+            for symbol in list {
+                let (old, new) = self
+                    .last_prices
+                    .get(symbol)
+                    .copied()
+                    .unwrap_or((None, None));
+                symbols.push(symbol.as_str());
+                changes.push(change);
+                old_last_prices.push(old);
+                new_last_prices.push(new);
+            }
+        }
+
+        DataFrame::new(vec![
+            Series::new("symbol", &symbols),
+            Series::new("change", &changes),
+            Series::new("old_last_price", &old_last_prices),
+            Series::new("new_last_price", &new_last_prices),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TurnoverPrice {
+    #[default]
+    AveragePrice,
+    LastPrice,
+}
+
+pub fn quote_to_polars_df_with_turnover(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    quote_to_polars_df_with_turnover_by(quote, TurnoverPrice::default())
+}
+
+// `turnover` is price * volume in f64. Past 2^53 the product can't represent
+// every integer, so for very high-volume, high-priced names it is approximate
+// to roughly 16 significant digits.
+pub fn quote_to_polars_df_with_turnover_by(
+    quote: &Quotes,
+    price: TurnoverPrice,
+) -> Result<DataFrame, PolarsError> {
+    let turnovers: Vec<f64> = quote
+        .instruments
+        .values()
+        .map(|q| {
+            let price = match price {
+                TurnoverPrice::AveragePrice => q.average_price,
+                TurnoverPrice::LastPrice => q.last_price,
+            };
+            price * q.volume as f64
+        })
+        .collect();
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(Series::new("turnover", &turnovers));
+    DataFrame::new(series_buf)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidityWeights {
+    pub volume: f64,
+    pub depth: f64,
+    pub inverse_spread: f64,
+}
+
+impl Default for LiquidityWeights {
+    fn default() -> Self {
+        LiquidityWeights {
+            volume: 1.0,
+            depth: 1.0,
+            inverse_spread: 1.0,
+        }
+    }
+}
+
+// `liquidity_score` is the weighted sum of volume, total book quantity (both
+// sides) and 1 / best spread, each first divided by its maximum across the
+// batch so every component lies in [0, 1]. A missing or non-positive spread
+// contributes zero, as does any component whose batch maximum is zero.
+pub fn quote_to_polars_df_with_liquidity(
+    quote: &Quotes,
+    weights: LiquidityWeights,
+) -> Result<DataFrame, PolarsError> {
+    let components: Vec<[f64; 3]> = quote
+        .instruments
+        .values()
+        .map(|q| {
+            let depth = saturating_sum([q.total_buy_depth(), q.total_sell_depth()]);
+            let inverse_spread = q
+                .best_spread()
+                .filter(|&spread| spread > 0.0)
+                .map_or(0.0, |spread| 1.0 / spread);
+            [q.volume as f64, depth as f64, inverse_spread]
+        })
+        .collect();
+    let mut maxima = [0.0f64; 3];
+    for row in &components {
+        for (max, value) in maxima.iter_mut().zip(row) {
+            *max = max.max(*value);
+        }
+    }
+    let weights = [weights.volume, weights.depth, weights.inverse_spread];
+    let scores: Vec<f64> = components
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(maxima)
+                .zip(weights)
+                .map(|((value, max), weight)| if max > 0.0 { weight * value / max } else { 0.0 })
+                .sum()
+        })
+        .collect();
+
+    let mut series_buf = quote_series(quote);
+    series_buf.push(Series::new("liquidity_score", &scores));
+    DataFrame::new(series_buf)
+}
+
+// Regular quotes carry five levels a side; NSE's 20-level depth feed more.
+pub const DEFAULT_DEPTH_LEVELS: usize = 5;
+
+// Flattens the first `depth_levels` of each side of the book into
+// `buy_price_1`, `buy_quantity_1`, `buy_orders_1`, ...,
+// `sell_orders_{depth_levels}`; levels an instrument doesn't have are null
+// and deeper ones are dropped. With `depth_as_u32` the quantity and
+// orders columns are `UInt32` instead of `UInt64`; a value above `u32::MAX`
+// is an error naming the symbol and column, never truncated.
+pub fn quote_to_polars_df_with_depth(
+    quote: &Quotes,
+    depth_levels: usize,
+    depth_as_u32: bool,
+) -> Result<DataFrame, PolarsError> {
+    let mut series_buf = quote_series(quote);
+    series_buf.extend(depth_series(
+        &quote_books(quote),
+        depth_levels,
+        depth_as_u32,
+    )?);
+    DataFrame::new(series_buf)
+}
+
+fn quote_books(quote: &Quotes) -> Vec<(&str, &Depth)> {
+    quote
+        .instruments
+        .iter()
+        .map(|(symbol, q)| (symbol.as_str(), &q.depth))
+        .collect()
+}
+
+// One (label, book) per row; the label only names the row in errors.
+fn depth_series<L: fmt::Display>(
+    books: &[(L, &Depth)],
+    depth_levels: usize,
+    depth_as_u32: bool,
+) -> Result<Vec<Series>, PolarsError> {
+    let counts_series = |name: String, values: Vec<Option<u64>>| -> Result<Series, PolarsError> {
+        if !depth_as_u32 {
+            return Ok(Series::new(&name, &values));
+        }
+        let narrowed = values
+            .iter()
+            .zip(books.iter().map(|(label, _)| label))
+            .map(|(value, symbol)| {
+                value
+                    .map(|v| {
+                        u32::try_from(v).map_err(|_| {
+                            PolarsError::ComputeError(
+                                format!("{symbol}: {name} value {v} does not fit in u32").into(),
+                            )
+                        })
+                    })
+                    .transpose()
+            })
+            .collect::<Result<Vec<Option<u32>>, PolarsError>>()?;
+        Ok(Series::new(&name, &narrowed))
+    };
+
+    let mut series_buf = Vec::with_capacity(2 * 3 * depth_levels);
+    for (prefix, side) in [("buy", Side::Buy), ("sell", Side::Sell)] {
+        for level in 0..depth_levels {
+            let entries: Vec<Option<&OrderDepth>> = books
+                .iter()
+                .map(|(_, depth)| match side {
+                    Side::Buy => depth.buy.get(level),
+                    Side::Sell => depth.sell.get(level),
+                })
+                .collect();
+            let prices: Vec<Option<f64>> = entries.iter().map(|d| d.map(|d| d.price)).collect();
+            let quantities = entries.iter().map(|d| d.map(|d| d.quantity)).collect();
+            let orders = entries.iter().map(|d| d.map(|d| d.orders)).collect();
+            let n = level + 1;
+            series_buf.push(Series::new(&format!("{prefix}_price_{n}"), &prices));
+            series_buf.push(counts_series(format!("{prefix}_quantity_{n}"), quantities)?);
+            series_buf.push(counts_series(format!("{prefix}_orders_{n}"), orders)?);
+        }
+    }
+    Ok(series_buf)
+}
+
+// For feeds where a price of exactly 0.0 means "no trade yet": zero
+// `last_price`, `average_price` and OHLC values become null. Other float
+// columns (`net_change`, circuit limits) keep their zeros.
+pub fn quote_to_polars_df_zero_as_null(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let mut df = DataFrame::new(quote_series(quote))?;
+    null_zero_prices(&mut df)?;
+    Ok(df)
+}
+
+fn null_zero_prices(df: &mut DataFrame) -> Result<(), PolarsError> {
+    for name in [
+        "last_price",
+        "average_price",
+        "open",
+        "high",
+        "low",
+        "close",
+    ] {
+        let nulled: Float64Chunked = df
+            .column(name)?
+            .f64()?
+            .into_iter()
+            .map(|v| v.filter(|&x| x != 0.0))
+            .collect();
+        df.with_column(nulled.with_name(name).into_series())?;
+    }
+    Ok(())
+}
+
+// Broadcasts each value into a full-height column, e.g. a `run_id` tag. A
+// column with an existing name is replaced.
+pub fn with_constant_columns(
+    df: &mut DataFrame,
+    cols: &[(&str, AnyValue)],
+) -> Result<(), PolarsError> {
+    let height = df.height();
+    for (name, value) in cols {
+        let single = Series::from_any_values(name, std::slice::from_ref(value), true)?;
+        df.with_column(single.new_from_index(0, height))?;
+    }
+    Ok(())
+}
+
+// Plain-Vec extraction for handing a column to non-Polars code. The column's
+// dtype must match exactly (no casting), and a null is an error naming the
+// row; fill nulls first if a default is wanted.
+pub fn df_column_f64(df: &DataFrame, name: &str) -> Result<Vec<f64>, PolarsError> {
+    collect_non_null(name, df.column(name)?.f64()?.into_iter())
+}
+
+pub fn df_column_u64(df: &DataFrame, name: &str) -> Result<Vec<u64>, PolarsError> {
+    collect_non_null(name, df.column(name)?.u64()?.into_iter())
+}
+
+pub fn df_column_str(df: &DataFrame, name: &str) -> Result<Vec<String>, PolarsError> {
+    collect_non_null(
+        name,
+        df.column(name)?
+            .str()?
+            .into_iter()
+            .map(|v| v.map(str::to_owned)),
+    )
+}
+
+fn collect_non_null<T>(
+    name: &str,
+    values: impl Iterator<Item = Option<T>>,
+) -> Result<Vec<T>, PolarsError> {
+    values
+        .enumerate()
+        .map(|(row, v)| {
+            v.ok_or_else(|| PolarsError::ComputeError(format!("{name}: null at row {row}").into()))
+        })
+        .collect()
+}
+
+// One place to combine the `quote_to_polars_df_with_*` variants. The default
+// options reproduce the canonical frame (`quote_to_polars_df_from_series_v2`):
+// no parsed timestamps, no depth, no derived columns, zeros kept, unsigned
+// integers and map order. Enabled extras are appended after the standard
+// columns in the order timestamp_parsed, depth, derived.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionOptions {
+    parse_timestamps: bool,
+    include_depth: Option<usize>,
+    derived: Vec<DerivedColumn>,
+    null_on_zero_price: bool,
+    signed_ints: bool,
+    sort_by: Option<String>,
+}
+
+impl ConversionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds a microsecond `timestamp_parsed` column.
+    pub fn parse_timestamps(mut self, yes: bool) -> Self {
+        self.parse_timestamps = yes;
+        self
+    }
+
+    // Adds `levels` of flattened depth per side; see
+    // `quote_to_polars_df_with_depth`.
+    pub fn include_depth(mut self, levels: usize) -> Self {
+        self.include_depth = Some(levels);
+        self
+    }
+
+    pub fn derived(mut self, columns: Vec<DerivedColumn>) -> Self {
+        self.derived = columns;
+        self
+    }
+
+    // Same columns as `quote_to_polars_df_zero_as_null`.
+    pub fn null_on_zero_price(mut self, yes: bool) -> Self {
+        self.null_on_zero_price = yes;
+        self
+    }
+
+    // Every unsigned column as its signed counterpart, depth included.
+    pub fn signed_ints(mut self, yes: bool) -> Self {
+        self.signed_ints = yes;
+        self
+    }
+
+    // Ascending sort on any output column, applied last.
+    pub fn sort_by(mut self, column: &str) -> Self {
+        self.sort_by = Some(column.to_owned());
+        self
+    }
+}
+
+pub fn quote_to_polars_df_with_options(
+    quote: &Quotes,
+    opts: &ConversionOptions,
+) -> Result<DataFrame, PolarsError> {
+    let mut series_buf = quote_series(quote);
+    if opts.parse_timestamps {
+        series_buf.push(parsed_timestamp_series(quote, TimeUnit::Microseconds)?);
+    }
+    if let Some(levels) = opts.include_depth {
+        series_buf.extend(depth_series(&quote_books(quote), levels, false)?);
+    }
+    if opts.signed_ints {
+        series_buf = series_buf
+            .into_iter()
+            .map(signed_if_unsigned)
+            .collect::<Result<_, _>>()?;
+    }
+    let mut df = DataFrame::new(series_buf)?;
+    append_derived_columns(&mut df, quote, &opts.derived)?;
+    if opts.null_on_zero_price {
+        null_zero_prices(&mut df)?;
+    }
+    match &opts.sort_by {
+        Some(column) => df.sort([column.as_str()], Default::default()),
+        None => Ok(df),
+    }
+}
+
+pub fn non_finite_to_null(df: &mut DataFrame) -> Result<(), PolarsError> {
+    let float_columns: Vec<String> = df
+        .get_columns()
+        .iter()
+        .filter(|s| s.dtype() == &DataType::Float64)
+        .map(|s| s.name().to_owned())
+        .collect();
+    for name in float_columns {
+        let finite: Float64Chunked = df
+            .column(&name)?
+            .f64()?
+            .into_iter()
+            .map(|v| v.filter(|x| x.is_finite()))
+            .collect();
+        df.with_column(finite.with_name(&name).into_series())?;
+    }
+    Ok(())
+}
+
+// Display helper; the frame's values are replaced, so round a clone when the
+// exact figures are still needed.
+pub fn round_float_columns(
+    df: &mut DataFrame,
+    decimals: u32,
+    columns: &[&str],
+) -> Result<(), PolarsError> {
+    for name in columns {
+        let column = df.column(name)?;
+        if !column.dtype().is_float() {
+            return Err(PolarsError::SchemaMismatch(
+                format!("column {name} is {}, expected a float", column.dtype()).into(),
+            ));
+        }
+        let rounded = column.round(decimals)?;
+        df.with_column(rounded)?;
+    }
+    Ok(())
+}
+
+pub fn quote_to_ohlc_summary(quote: &Quotes) -> Result<DataFrame, PolarsError> {
+    let instruments = quote.instruments.len() as u64;
+    let total_volume = saturating_sum(quote.instruments.values().map(|q| q.volume));
+    let total_oi = saturating_sum(quote.instruments.values().map(|q| q.oi));
+    let instruments_up = quote
+        .instruments
+        .values()
+        .filter(|q| q.net_change > 0.0)
+        .count() as u64;
+    let instruments_down = quote
+        .instruments
+        .values()
+        .filter(|q| q.net_change < 0.0)
+        .count() as u64;
+    let avg_net_change = if instruments == 0 {
+        None
+    } else {
+        let total: f64 = quote.instruments.values().map(|q| q.net_change).sum();
+        Some(total / instruments as f64)
+    };
+
+    DataFrame::new(vec![
+        Series::new("instruments", &[instruments]),
+        Series::new("total_volume", &[total_volume]),
+        Series::new("total_oi", &[total_oi]),
+        Series::new("instruments_up", &[instruments_up]),
+        Series::new("instruments_down", &[instruments_down]),
+        Series::new("avg_net_change", &[avg_net_change]),
+    ])
+}
+
+pub fn quote_opt_to_polars_df(quote: &QuotesOpt) -> Result<DataFrame, PolarsError> {
+    let len = quote.instruments.len();
+    let mut symbols = Vec::with_capacity(len);
+    let mut instrument_tokens = Vec::with_capacity(len);
+    let mut timestamps = Vec::with_capacity(len);
+    let mut last_trade_times = Vec::with_capacity(len);
+    let mut last_prices = Vec::with_capacity(len);
+    let mut last_quantities = Vec::with_capacity(len);
+    let mut buy_quantities = Vec::with_capacity(len);
+    let mut sell_quantities = Vec::with_capacity(len);
+    let mut volumes = Vec::with_capacity(len);
+    let mut average_prices = Vec::with_capacity(len);
+    let mut ois = Vec::with_capacity(len);
+    let mut oi_day_highs = Vec::with_capacity(len);
+    let mut oi_day_lows = Vec::with_capacity(len);
+    let mut net_changes = Vec::with_capacity(len);
+    let mut lower_circuit_limits = Vec::with_capacity(len);
+    let mut upper_circuit_limits = Vec::with_capacity(len);
+    let mut opens = Vec::with_capacity(len);
+    let mut highs = Vec::with_capacity(len);
+    let mut lows = Vec::with_capacity(len);
+    let mut closes = Vec::with_capacity(len);
+
+    for (symbol, q) in &quote.instruments {
+        symbols.push(symbol.as_str());
+        instrument_tokens.push(q.instrument_token);
+        timestamps.push(q.timestamp.as_deref());
+        last_trade_times.push(q.last_trade_time.as_deref());
+        last_prices.push(q.last_price);
+        last_quantities.push(q.last_quantity);
+        buy_quantities.push(q.buy_quantity);
+        sell_quantities.push(q.sell_quantity);
+        volumes.push(q.volume);
+        average_prices.push(q.average_price);
+        ois.push(q.oi);
+        oi_day_highs.push(q.oi_day_high);
+        oi_day_lows.push(q.oi_day_low);
+        net_changes.push(q.net_change);
+        lower_circuit_limits.push(q.lower_circuit_limit);
+        upper_circuit_limits.push(q.upper_circuit_limit);
+        opens.push(q.ohlc.as_ref().map(|o| o.open));
+        highs.push(q.ohlc.as_ref().map(|o| o.high));
+        lows.push(q.ohlc.as_ref().map(|o| o.low));
+        closes.push(q.ohlc.as_ref().map(|o| o.close));
+    }
+
+    DataFrame::new(vec![
+        Series::new("symbol", &symbols),
+        Series::new("instrument_token", &instrument_tokens),
+        Series::new("timestamp", &timestamps),
+        Series::new("last_trade_time", &last_trade_times),
+        Series::new("last_price", &last_prices),
+        Series::new("last_quantity", &last_quantities),
+        Series::new("buy_quantity", &buy_quantities),
+        Series::new("sell_quantity", &sell_quantities),
+        Series::new("volume", &volumes),
+        Series::new("average_price", &average_prices),
+        Series::new("oi", &ois),
+        Series::new("oi_day_high", &oi_day_highs),
+        Series::new("oi_day_low", &oi_day_lows),
+        Series::new("net_change", &net_changes),
+        Series::new("lower_circuit_limit", &lower_circuit_limits),
+        Series::new("upper_circuit_limit", &upper_circuit_limits),
+        Series::new("open", &opens),
+        Series::new("high", &highs),
+        Series::new("low", &lows),
+        Series::new("close", &closes),
+    ])
+}
+
+fn check_quotes_schema(df: &DataFrame) -> Result<(), PolarsError> {
+    for (name, dtype) in quotes_schema().iter() {
+        let column = df.column(name)?;
+        if column.dtype() != dtype {
+            return Err(PolarsError::SchemaMismatch(
+                format!(
+                    "column {:?} has dtype {}, expected {}",
+                    name.as_str(),
+                    column.dtype(),
+                    dtype
+                )
+                .into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn dataframe_to_quotes(df: &DataFrame) -> Result<Quotes, PolarsError> {
+    check_quotes_schema(df)?;
+
+    let symbols = df.column("symbol")?.str()?;
+    let instrument_tokens = df.column("instrument_token")?.u64()?;
+    let timestamps = df.column("timestamp")?.str()?;
+    let last_trade_times = df.column("last_trade_time")?.str()?;
+    let last_prices = df.column("last_price")?.f64()?;
+    let last_quantities = df.column("last_quantity")?.u64()?;
+    let buy_quantities = df.column("buy_quantity")?.u64()?;
+    let sell_quantities = df.column("sell_quantity")?.u64()?;
+    let volumes = df.column("volume")?.u64()?;
+    let average_prices = df.column("average_price")?.f64()?;
+    let ois = df.column("oi")?.u64()?;
+    let oi_day_highs = df.column("oi_day_high")?.u64()?;
+    let oi_day_lows = df.column("oi_day_low")?.u64()?;
+    let net_changes = df.column("net_change")?.f64()?;
+    let lower_circuit_limits = df.column("lower_circuit_limit")?.f64()?;
+    let upper_circuit_limits = df.column("upper_circuit_limit")?.f64()?;
+    let opens = df.column("open")?.f64()?;
+    let highs = df.column("high")?.f64()?;
+    let lows = df.column("low")?.f64()?;
+    let closes = df.column("close")?.f64()?;
+
+    let mut instruments = HashMap::with_capacity(df.height());
+    for i in 0..df.height() {
+        let symbol = non_null(symbols.get(i), "symbol", i)?;
+        instruments.insert(
+            symbol.to_owned(),
+            QuotesData {
+                instrument_token: non_null(instrument_tokens.get(i), "instrument_token", i)?,
+                timestamp: non_null(timestamps.get(i), "timestamp", i)?.to_owned(),
+                last_trade_time: non_null(last_trade_times.get(i), "last_trade_time", i)?
+                    .to_owned(),
+                last_price: non_null(last_prices.get(i), "last_price", i)?,
+                last_quantity: non_null(last_quantities.get(i), "last_quantity", i)?,
+                buy_quantity: non_null(buy_quantities.get(i), "buy_quantity", i)?,
+                sell_quantity: non_null(sell_quantities.get(i), "sell_quantity", i)?,
+                volume: non_null(volumes.get(i), "volume", i)?,
+                average_price: non_null(average_prices.get(i), "average_price", i)?,
+                oi: non_null(ois.get(i), "oi", i)?,
+                oi_day_high: non_null(oi_day_highs.get(i), "oi_day_high", i)?,
+                oi_day_low: non_null(oi_day_lows.get(i), "oi_day_low", i)?,
+                net_change: non_null(net_changes.get(i), "net_change", i)?,
+                lower_circuit_limit: non_null(
+                    lower_circuit_limits.get(i),
+                    "lower_circuit_limit",
+                    i,
+                )?,
+                upper_circuit_limit: non_null(
+                    upper_circuit_limits.get(i),
+                    "upper_circuit_limit",
+                    i,
+                )?,
+                ohlc: match (opens.get(i), highs.get(i), lows.get(i), closes.get(i)) {
+                    (None, None, None, None) => None,
+                    (open, high, low, close) => Some(OhlcInner {
+                        open: non_null(open, "open", i)?,
+                        high: non_null(high, "high", i)?,
+                        low: non_null(low, "low", i)?,
+                        close: non_null(close, "close", i)?,
+                    }),
+                },
+                depth: Depth::default(),
+                tradable: None,
+                mode: None,
+            },
+        );
+    }
+
+    Ok(Quotes { instruments })
+}
+
+fn non_null<T>(value: Option<T>, column: &str, row: usize) -> Result<T, PolarsError> {
+    value.ok_or_else(|| {
+        PolarsError::ComputeError(format!("null in column {:?} at row {}", column, row).into())
+    })
+}
+
+// Columns are named and typed like the quote frame's, with null OHLC for
+// ticks that don't carry it (LTP mode). The book is left out, as in the base
+// quote frame; see `ticks_to_polars_df_with_depth`.
+pub fn ticks_to_polars_df(ticks: &[Tick]) -> Result<DataFrame, PolarsError> {
+    let len = ticks.len();
+    let mut instrument_tokens = Vec::with_capacity(len);
+    let mut last_prices = Vec::with_capacity(len);
+    let mut volumes = Vec::with_capacity(len);
+    let mut opens = Vec::with_capacity(len);
+    let mut highs = Vec::with_capacity(len);
+    let mut lows = Vec::with_capacity(len);
+    let mut closes = Vec::with_capacity(len);
+
+    for t in ticks {
+        instrument_tokens.push(t.instrument_token);
+        last_prices.push(t.last_price);
+        volumes.push(t.volume);
+        opens.push(t.ohlc.as_ref().map(|o| o.open));
+        highs.push(t.ohlc.as_ref().map(|o| o.high));
+        lows.push(t.ohlc.as_ref().map(|o| o.low));
+        closes.push(t.ohlc.as_ref().map(|o| o.close));
+    }
+
+    DataFrame::new(vec![
+        Series::new("instrument_token", &instrument_tokens),
+        Series::new("last_price", &last_prices),
+        Series::new("volume", &volumes),
+        Series::new("open", &opens),
+        Series::new("high", &highs),
+        Series::new("low", &lows),
+        Series::new("close", &closes),
+    ])
+}
+
+// Adds the same flattened depth columns as `quote_to_polars_df_with_depth`,
+// with errors naming the instrument token.
+pub fn ticks_to_polars_df_with_depth(
+    ticks: &[Tick],
+    depth_levels: usize,
+) -> Result<DataFrame, PolarsError> {
+    let mut df = ticks_to_polars_df(ticks)?;
+    let books: Vec<(u64, &Depth)> = ticks
+        .iter()
+        .map(|t| (t.instrument_token, &t.depth))
+        .collect();
+    for series in depth_series(&books, depth_levels, false)? {
+        df.with_column(series)?;
+    }
+    Ok(df)
+}
+
+pub fn candles_to_polars_df(candles: &[Candle]) -> Result<DataFrame, PolarsError> {
+    let len = candles.len();
+    let mut timestamps = Vec::with_capacity(len);
+    let mut opens = Vec::with_capacity(len);
+    let mut highs = Vec::with_capacity(len);
+    let mut lows = Vec::with_capacity(len);
+    let mut closes = Vec::with_capacity(len);
+    let mut volumes = Vec::with_capacity(len);
+    let mut ois = Vec::with_capacity(len);
+
+    for c in candles {
+        timestamps.push(c.timestamp);
+        opens.push(c.open);
+        highs.push(c.high);
+        lows.push(c.low);
+        closes.push(c.close);
+        volumes.push(c.volume);
+        ois.push(c.oi);
+    }
+
+    let mut columns = vec![
+        Series::new("timestamp", &timestamps),
+        Series::new("open", &opens),
+        Series::new("high", &highs),
+        Series::new("low", &lows),
+        Series::new("close", &closes),
+        Series::new("volume", &volumes),
+    ];
+    if ois.iter().any(Option::is_some) {
+        columns.push(Series::new("oi", &ois));
+    }
+
+    let df = DataFrame::new(columns)?;
+    df.sort(["timestamp"], Default::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_historical_candles() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/historical_minute.json")?;
+        let deserialized: Historical = serde_json::from_reader(jsonfile)?;
+        let candles = deserialized.data.unwrap().candles;
+        assert_eq!(candles.len(), 3);
+        assert_eq!(
+            candles[0],
+            Candle {
+                timestamp: NaiveDate::from_ymd_opt(2017, 12, 15)
+                    .unwrap()
+                    .and_hms_opt(9, 15, 0)
+                    .unwrap(),
+                open: 1704.5,
+                high: 1705.0,
+                low: 1699.25,
+                close: 1702.8,
+                volume: 2499,
+                oi: None,
+            }
+        );
+        let df = candles_to_polars_df(&candles)?;
+        assert_eq!(df.shape(), (3, 6));
+        assert_eq!(
+            df.column("timestamp")?.dtype(),
+            &DataType::Datetime(TimeUnit::Milliseconds, None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_oi_delta() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let current: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut previous = current.clone();
+        previous.instruments.remove("8960002");
+        previous.instruments.get_mut("8960258").unwrap().oi -= 100;
+
+        let df = quote_to_polars_df_with_oi_delta(&current, &previous)?;
+        assert_eq!(df.width(), 21);
+        assert_eq!(df.height(), current.instruments.len());
+
+        let symbols = df.column("symbol")?.str()?;
+        let oi_changes = df.column("oi_change")?.i64()?;
+        for (symbol, oi_change) in symbols.into_iter().zip(oi_changes) {
+            match symbol.unwrap() {
+                "8960002" => assert_eq!(oi_change, None),
+                "8960258" => assert_eq!(oi_change, Some(100)),
+                _ => assert_eq!(oi_change, Some(0)),
+            }
+        }
+
+        // Both sides above i64::MAX: the change itself is small and fits.
+        let mut huge_current = current.clone();
+        let mut huge_previous = current.clone();
+        huge_current.instruments.get_mut("8960258").unwrap().oi = u64::MAX;
+        huge_previous.instruments.get_mut("8960258").unwrap().oi = u64::MAX - 7;
+        let df = quote_to_polars_df_with_oi_delta(&huge_current, &huge_previous)?;
+        let row = df
+            .column("symbol")?
+            .str()?
+            .into_iter()
+            .position(|s| s == Some("8960258"))
+            .unwrap();
+        assert_eq!(df.column("oi_change")?.i64()?.get(row), Some(7));
+
+        huge_previous.instruments.get_mut("8960258").unwrap().oi = 0;
+        let err = quote_to_polars_df_with_oi_delta(&huge_current, &huge_previous).unwrap_err();
+        assert!(err.to_string().contains("8960258: oi_change"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_price_tick() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut current: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut previous = current.clone();
+        previous.instruments.remove("8960002");
+        previous.instruments.get_mut("8960258").unwrap().last_price -= 0.05;
+        previous.instruments.get_mut("8960514").unwrap().last_price += 0.05;
+        previous.instruments.get_mut("8960770").unwrap().last_price = f64::NAN;
+        current.instruments.get_mut("8962050").unwrap().last_price = f64::NAN;
+
+        let df = quote_to_polars_df_with_price_tick(&current, &previous)?;
+        assert_eq!(df.height(), current.instruments.len());
+
+        let symbols = df.column("symbol")?.str()?;
+        let directions = df.column("tick_direction")?.str()?;
+        for (symbol, direction) in symbols.into_iter().zip(directions) {
+            match symbol.unwrap() {
+                "8960002" | "8960770" | "8962050" => assert_eq!(direction, None),
+                "8960258" => assert_eq!(direction, Some("up")),
+                "8960514" => assert_eq!(direction, Some("down")),
+                _ => assert_eq!(direction, Some("unchanged")),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_opt_preserves_nulls() -> Result<(), Box<dyn Error>> {
+        let raw_data = r#"{
+            "NSE:INFY": {"instrument_token": 408065, "last_price": 1412.95, "oi": 0},
+            "NFO:NIFTY24SEPFUT": {"instrument_token": 8960002, "last_price": 25335.55}
+        }"#;
+        let quote: QuotesOpt = serde_json::from_str(raw_data)?;
+        let df = quote_opt_to_polars_df(&quote)?;
+        assert_eq!(df.shape(), (2, 20));
+
+        let symbols = df.column("symbol")?.str()?;
+        let ois = df.column("oi")?.u64()?;
+        for (symbol, oi) in symbols.into_iter().zip(ois) {
+            match symbol.unwrap() {
+                "NSE:INFY" => assert_eq!(oi, Some(0)),
+                _ => assert_eq!(oi, None),
+            }
+        }
+        assert_eq!(df.column("volume")?.null_count(), 2);
+        assert_eq!(df.column("close")?.null_count(), 2);
+        assert_eq!(df.column("last_price")?.null_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_arrow_schema() {
+        let schema = quotes_schema();
+        let arrow_schema = quotes_arrow_schema();
+        assert_eq!(arrow_schema.fields.len(), schema.len());
+        for (field, (name, _)) in arrow_schema.fields.iter().zip(schema.iter()) {
+            assert_eq!(field.name.as_str(), name.as_str());
+        }
+    }
+
+    #[test]
+    fn test_ipc_round_trip() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let depth: Vec<Series> = quotes
+            .instruments
+            .values()
+            .map(|q| {
+                let prices: Vec<f64> = q.depth.buy.iter().map(|d| d.price).collect();
+                Series::new("", &prices)
+            })
+            .collect();
+        let mut df = DataFrame::new(quote_series(&quotes))?;
+        df.with_column(Series::new("depth_buy_price", &depth))?;
+
+        let path = std::env::temp_dir().join("hello_test_ipc_round_trip.arrow");
+        write_df_to_ipc(&mut df, &path)?;
+        let read_back = read_df_from_ipc(&path)?;
+        std::fs::remove_file(&path)?;
+
+        assert!(matches!(
+            read_back.column("depth_buy_price")?.dtype(),
+            DataType::List(_)
+        ));
+        assert!(df.equals(&read_back));
+
+        let jsonfile = read_json_from_file("kiteconnect-mocks/historical_minute.json")?;
+        let historical: Historical = serde_json::from_reader(jsonfile)?;
+        let mut df = candles_to_polars_df(&historical.data.unwrap().candles)?;
+        let path = std::env::temp_dir().join("hello_test_ipc_round_trip_candles.arrow");
+        write_df_to_ipc(&mut df, &path)?;
+        let read_back = read_df_from_ipc(&path)?;
+        std::fs::remove_file(&path)?;
+        assert!(df.equals(&read_back));
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_quotes_from_gz_file() -> Result<(), Box<dyn Error>> {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let raw = std::fs::read("kiteconnect-mocks/quotes.json")?;
+        let path = std::env::temp_dir().join("hello_test_quotes.json.gz");
+        let mut encoder = GzEncoder::new(File::create(&path)?, Compression::default());
+        encoder.write_all(&raw)?;
+        encoder.finish()?;
+
+        let from_gz: Quotes = serde_json::from_reader(read_json_from_gz(&path)?)?;
+        let from_json: Quotes = serde_json::from_slice(&raw)?;
+        assert_eq!(from_gz, from_json);
+
+        let df = quotes_df_from_gz_file(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(df.shape(), (181, 20));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dual_timestamp() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        quotes.instruments.get_mut("8960002").unwrap().timestamp = "garbage".to_owned();
+
+        let df = quote_to_polars_df_dual_timestamp(&quotes)?;
+        assert_eq!(df.width(), 21);
+        assert_eq!(
+            df.column("timestamp_parsed")?.dtype(),
+            &DataType::Datetime(TimeUnit::Microseconds, None)
+        );
+        assert_eq!(df.column("timestamp_parsed")?.null_count(), 1);
+        assert_eq!(df.column("timestamp")?.null_count(), 0);
+
+        // Pre-open instruments have empty timestamps: null, not 1970.
+        quotes.instruments.get_mut("8960258").unwrap().timestamp = String::new();
+        let df = quote_to_polars_df_dual_timestamp(&quotes)?;
+        assert_eq!(df.column("timestamp_parsed")?.null_count(), 2);
+        let earliest = df
+            .column("timestamp_parsed")?
+            .datetime()?
+            .into_iter()
+            .flatten()
+            .min();
+        assert!(earliest.is_some_and(|ms| ms > 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dual_timestamp_units_agree() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let instants = |unit| -> Result<Vec<Option<NaiveDateTime>>, PolarsError> {
+            let df = quote_to_polars_df_dual_timestamp_with_unit(&quotes, unit)?;
+            let column = df.column("timestamp_parsed")?;
+            assert_eq!(column.dtype(), &DataType::Datetime(unit, None));
+            let ticks = column.datetime()?;
+            Ok(ticks
+                .into_iter()
+                .map(|v| {
+                    v.and_then(|v| {
+                        match unit {
+                            TimeUnit::Nanoseconds => chrono::DateTime::from_timestamp_nanos(v),
+                            TimeUnit::Microseconds => chrono::DateTime::from_timestamp_micros(v)?,
+                            TimeUnit::Milliseconds => chrono::DateTime::from_timestamp_millis(v)?,
+                        }
+                        .naive_utc()
+                        .into()
+                    })
+                })
+                .collect())
+        };
+
+        let expected: Vec<Option<NaiveDateTime>> = quotes
+            .instruments
+            .values()
+            .map(QuotesData::parsed_timestamp)
+            .collect();
+        assert!(expected.iter().all(Option::is_some));
+        for unit in [
+            TimeUnit::Nanoseconds,
+            TimeUnit::Microseconds,
+            TimeUnit::Milliseconds,
+        ] {
+            assert_eq!(instants(unit)?, expected, "{unit:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_ohlc_columns() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        quotes.instruments.get_mut("8960002").unwrap().ohlc = None;
+
+        let df = quote_to_polars_df_from_series_v2(quotes.clone())?;
+        let row = df
+            .column("symbol")?
+            .str()?
+            .into_iter()
+            .position(|s| s == Some("8960002"))
+            .unwrap();
+        for name in ["open", "high", "low", "close"] {
+            let column = df.column(name)?;
+            assert_eq!(column.null_count(), 1, "{name}");
+            assert_eq!(column.get(row)?, AnyValue::Null, "{name}");
+        }
+        let sorted = |df: DataFrame| df.sort(["symbol"], Default::default());
+        for other in [
+            quote_to_polars_df_from_series_v1(quotes.clone())?,
+            quote_to_polars_df_from_series_v3(quotes.clone())?,
+            quote_to_polars_df_from_rows_cols(quotes.clone())?,
+        ] {
+            assert!(sorted(other)?.equals_missing(&sorted(df.clone())?));
+        }
+
+        for q in quotes.instruments.values_mut() {
+            q.depth = Depth::default();
+        }
+        assert_eq!(dataframe_to_quotes(&df)?, quotes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dataframe_to_quotes() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let df = quote_to_polars_df_from_series_v1(quotes.clone())?;
+
+        for q in quotes.instruments.values_mut() {
+            q.depth = Depth::default();
+        }
+        assert_eq!(dataframe_to_quotes(&df)?, quotes);
+
+        let missing = df.drop("volume")?;
+        assert!(matches!(
+            dataframe_to_quotes(&missing),
+            Err(PolarsError::ColumnNotFound(_))
+        ));
+
+        let mut mistyped = df.clone();
+        mistyped.with_column(df.column("volume")?.cast(&DataType::Float64)?)?;
+        assert!(matches!(
+            dataframe_to_quotes(&mistyped),
+            Err(PolarsError::SchemaMismatch(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_conversion_variants_identical() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let variants = [
+            quote_to_polars_df_from_series_raghu(quotes.clone())?,
+            quote_to_polars_df_from_series_v0(quotes.clone())?,
+            quote_to_polars_df_from_series_v1(quotes.clone())?,
+            quote_to_polars_df_from_series_v2(quotes.clone())?,
+            quote_to_polars_df_from_series_v3(quotes.clone())?,
+            quote_to_polars_df_from_rows_cols(quotes.clone())?,
+        ]
+        .into_iter()
+        .map(|df| df.sort(["symbol"], Default::default()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        for df in &variants {
+            assert_eq!(df.shape(), (181, 20));
+            assert_eq!(df.schema(), quotes_schema());
+        }
+        for (i, a) in variants.iter().enumerate() {
+            for b in &variants[i + 1..] {
+                assert!(a.equals(b));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_circuit_flags() -> Result<(), Box<dyn Error>> {
+        let quote = |last_price, lower_circuit_limit, upper_circuit_limit| QuotesData {
+            last_price,
+            lower_circuit_limit,
+            upper_circuit_limit,
+            ..QuotesData::default()
+        };
+        let quotes = Quotes {
+            instruments: HashMap::from([
+                ("UPPER".to_owned(), quote(110.0, 90.0, 110.0)),
+                ("LOWER".to_owned(), quote(90.0, 90.0, 110.0)),
+                ("INSIDE".to_owned(), quote(100.0, 90.0, 110.0)),
+                ("NO_BAND".to_owned(), quote(100.0, 0.0, 0.0)),
+            ]),
+        };
+
+        let df = quote_to_polars_df_with_circuit_flags(&quotes)?;
+        let symbols = df.column("symbol")?.str()?;
+        let uppers = df.column("at_upper_circuit")?.bool()?;
+        let lowers = df.column("at_lower_circuit")?.bool()?;
+        for ((symbol, upper), lower) in symbols.into_iter().zip(uppers).zip(lowers) {
+            let expected = match symbol.unwrap() {
+                "UPPER" => (true, false),
+                "LOWER" => (false, true),
+                _ => (false, false),
+            };
+            assert_eq!((upper.unwrap(), lower.unwrap()), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_named() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+
+        let df = quote_to_polars_df_named(&quotes, &ColumnNames::default())?;
+        assert_eq!(df.schema(), quotes_schema());
+        assert!(df.equals(&DataFrame::new(quote_series(&quotes))?));
+
+        let names = ColumnNames {
+            last_price: "ltp".to_owned(),
+            oi: "open_interest".to_owned(),
+            ..ColumnNames::default()
+        };
+        let df = quote_to_polars_df_named(&quotes, &names)?;
+        assert_eq!(df.get_column_names()[4], "ltp");
+        assert_eq!(df.get_column_names()[10], "open_interest");
+        assert!(df.column("last_price").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_prev_close_named() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let expected = DataFrame::new(quote_series(&quotes))?;
+
+        let df = quote_to_polars_df_prev_close_named(&quotes, false)?;
+        assert_eq!(df.width(), 20);
+        assert_eq!(df.get_column_names()[19], "prev_close");
+        assert!(df.column("close").is_err());
+        assert!(df
+            .column("prev_close")?
+            .equals(&expected.column("close")?.clone().with_name("prev_close")));
+
+        let df = quote_to_polars_df_prev_close_named(&quotes, true)?;
+        assert_eq!(df.width(), 21);
+        assert_eq!(&df.get_column_names()[18..], ["low", "close", "prev_close"]);
+        assert!(df.column("close")?.equals(expected.column("close")?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_diff() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let previous: Quotes = serde_json::from_reader(jsonfile)?;
+        assert!(previous.diff(&previous).is_empty());
+
+        let mut current = previous.clone();
+        current.instruments.remove("8960002");
+        current.instruments.get_mut("8960258").unwrap().last_price = 1.5;
+        current.instruments.get_mut("8960514").unwrap().volume += 1;
+        current
+            .instruments
+            .insert("NSE:INFY".to_owned(), QuotesData::default());
+
+        let diff = current.diff(&previous);
+        assert_eq!(diff.added, vec!["NSE:INFY".to_owned()]);
+        assert_eq!(diff.removed, vec!["8960002".to_owned()]);
+        assert_eq!(
+            diff.changed,
+            vec!["8960258".to_owned(), "8960514".to_owned()]
+        );
+
+        let df = diff.to_dataframe()?;
+        assert_eq!(df.shape(), (4, 4));
+        let old = df.column("old_last_price")?.f64()?;
+        let new = df.column("new_last_price")?.f64()?;
+        assert_eq!((old.get(0), new.get(0)), (None, Some(0.0)));
+        assert_eq!(
+            (old.get(1), new.get(1)),
+            (Some(previous.instruments["8960002"].last_price), None)
+        );
+        assert_eq!(
+            (old.get(2), new.get(2)),
+            (Some(previous.instruments["8960258"].last_price), Some(1.5))
+        );
+
+        let mut edited = diff.clone();
+        edited.changed.retain(|symbol| symbol != "8960514");
+        edited.changed.push("NSE:UNKNOWN".to_owned());
+        let df = edited.to_dataframe()?;
+        assert_eq!(df.height(), 4);
+        let symbols = df.column("symbol")?.str()?;
+        assert_eq!(symbols.get(3), Some("NSE:UNKNOWN"));
+        assert_eq!(df.column("old_last_price")?.f64()?.get(3), None);
+        assert_eq!(df.column("new_last_price")?.f64()?.get(3), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_floats() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        {
+            let q = quotes.instruments.get_mut("8960002").unwrap();
+            q.last_price = f64::NAN;
+            q.ohlc.as_mut().unwrap().high = f64::INFINITY;
+            q.depth.sell[0].price = f64::NEG_INFINITY;
+        }
+
+        let mut df = DataFrame::new(quote_series(&quotes))?;
+        non_finite_to_null(&mut df)?;
+        assert_eq!(df.column("last_price")?.null_count(), 1);
+        assert_eq!(df.column("high")?.null_count(), 1);
+        assert_eq!(df.column("low")?.null_count(), 0);
+
+        assert_eq!(quotes.sanitize_floats(), 3);
+        let q = &quotes.instruments["8960002"];
+        assert_eq!(q.last_price, 0.0);
+        assert_eq!(q.ohlc.as_ref().unwrap().high, 0.0);
+        assert_eq!(q.depth.sell[0].price, 0.0);
+        assert_eq!(quotes.sanitize_floats(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ticks_to_polars_df() -> Result<(), Box<dyn Error>> {
+        let raw_data = r#"[
+            {"instrument_token": 408065, "last_price": 1412.95},
+            {
+                "instrument_token": 8960002,
+                "last_price": 25335.55,
+                "volume": 12,
+                "ohlc": {"open": 25300, "high": 25400, "low": 25250.5, "close": 25280},
+                "depth": {
+                    "buy": [{"price": 25335.5, "quantity": 25, "orders": 1}],
+                    "sell": [{"price": 25336, "quantity": 50, "orders": 2}]
+                }
+            }
+        ]"#;
+        let ticks: Vec<Tick> = serde_json::from_str(raw_data)?;
+        assert_eq!(ticks[0].depth, Depth::default());
+        assert_eq!(ticks[1].depth.sell[0].quantity, 50);
+
+        assert_eq!(ticks[0].ohlc, None);
+
+        let df = ticks_to_polars_df(&ticks)?;
+        assert_eq!(df.shape(), (2, 7));
+        let quote_schema = quotes_schema();
+        for (name, dtype) in df.schema().iter() {
+            assert_eq!(quote_schema.get(name), Some(dtype));
+        }
+        for name in ["open", "high", "low", "close"] {
+            let column = df.column(name)?.f64()?;
+            assert_eq!(column.get(0), None, "{name}");
+            assert!(column.get(1).is_some(), "{name}");
+        }
+
+        let df = ticks_to_polars_df_with_depth(&ticks, 2)?;
+        assert_eq!(df.shape(), (2, 7 + 2 * 2 * 3));
+        let sell_quantities = df.column("sell_quantity_1")?.u64()?;
+        assert_eq!(sell_quantities.get(0), None);
+        assert_eq!(sell_quantities.get(1), Some(50));
+        assert_eq!(df.column("buy_price_2")?.null_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_series() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let df = DataFrame::new(quote_series(&quotes))?;
+        for (name, _) in quotes_schema().iter() {
+            let series = quotes.column_series(name)?;
+            assert!(series.equals(df.column(name)?));
+        }
+        assert!(matches!(
+            quotes.column_series("ltp"),
+            Err(PolarsError::ColumnNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ohlc_summary() -> Result<(), Box<dyn Error>> {
+        let quote = |net_change, volume, oi| QuotesData {
+            net_change,
+            volume,
+            oi,
+            ..QuotesData::default()
+        };
+        let quotes = Quotes {
+            instruments: HashMap::from([
+                ("A".to_owned(), quote(2.0, 100, 10)),
+                ("B".to_owned(), quote(-1.0, 200, 0)),
+                ("C".to_owned(), quote(0.0, 300, 5)),
+                ("D".to_owned(), quote(3.0, 400, 0)),
+            ]),
+        };
+        let df = quote_to_ohlc_summary(&quotes)?;
+        assert_eq!(df.shape(), (1, 6));
+        assert_eq!(df.column("total_volume")?.u64()?.get(0), Some(1000));
+        assert_eq!(df.column("total_oi")?.u64()?.get(0), Some(15));
+        assert_eq!(df.column("instruments_up")?.u64()?.get(0), Some(2));
+        assert_eq!(df.column("instruments_down")?.u64()?.get(0), Some(1));
+        assert_eq!(df.column("avg_net_change")?.f64()?.get(0), Some(1.0));
+
+        let empty = quote_to_ohlc_summary(&Quotes::default())?;
+        assert_eq!(empty.column("avg_net_change")?.null_count(), 1);
+
+        let huge = Quotes {
+            instruments: HashMap::from([
+                ("A".to_owned(), quote(0.0, u64::MAX - 1, u64::MAX)),
+                ("B".to_owned(), quote(0.0, 2, 1)),
+            ]),
+        };
+        let df = quote_to_ohlc_summary(&huge)?;
+        assert_eq!(df.column("total_volume")?.u64()?.get(0), Some(u64::MAX));
+        assert_eq!(df.column("total_oi")?.u64()?.get(0), Some(u64::MAX));
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_derived_columns() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        {
+            let q = quotes.instruments.get_mut("8960002").unwrap();
+            q.depth.buy.clear();
+            q.buy_quantity = 0;
+            q.sell_quantity = 0;
+        }
+
+        let mut df = DataFrame::new(quote_series(&quotes))?;
+        let base_width = df.width();
+        append_derived_columns(&mut df, &quotes, &[])?;
+        assert_eq!(df.width(), base_width);
+
+        let all = [
+            DerivedColumn::ChangePct,
+            DerivedColumn::Spread,
+            DerivedColumn::MidPrice,
+            DerivedColumn::MicroPrice,
+            DerivedColumn::DepthImbalance,
+        ];
+        append_derived_columns(&mut df, &quotes, &all)?;
+        assert_eq!(df.width(), base_width + all.len());
+
+        let symbols = df.column("symbol")?.str()?;
+        let spread = df.column("spread")?.f64()?;
+        let mid = df.column("mid_price")?.f64()?;
+        let change = df.column("change_pct")?.f64()?;
+        let imbalance = df.column("depth_imbalance")?.f64()?;
+        for i in 0..df.height() {
+            let q = &quotes.instruments[symbols.get(i).unwrap()];
+            if symbols.get(i) == Some("8960002") {
+                assert_eq!(
+                    (spread.get(i), mid.get(i), imbalance.get(i)),
+                    (None, None, None)
+                );
+                continue;
+            }
+            let (bid, ask) = (q.depth.buy[0].price, q.depth.sell[0].price);
+            assert_eq!(spread.get(i), Some(ask - bid));
+            assert_eq!(mid.get(i), Some((ask + bid) / 2.0));
+            assert_eq!(
+                change.get(i).is_some(),
+                q.ohlc.as_ref().is_some_and(|o| o.close != 0.0)
+            );
+        }
+
+        let err = append_derived_columns(&mut df.head(Some(1)), &quotes, &all);
+        assert!(matches!(err, Err(PolarsError::ShapeMismatch(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_exchange_columns() -> Result<(), Box<dyn Error>> {
+        let mut quotes = Quotes::default();
+        for symbol in ["NSE:INFY", "NFO:NIFTY24SEP25000CE", "BSE:A:B", "408065"] {
+            quotes
+                .instruments
+                .insert(symbol.to_owned(), QuotesData::default());
+        }
+
+        let df = quote_to_polars_df_with_exchange(&quotes)?;
+        assert!(matches!(
+            df.column("exchange")?.dtype(),
+            DataType::Categorical(_, _)
+        ));
+        let symbols = df.column("symbol")?.str()?;
+        let exchanges = df.column("exchange")?.cast(&DataType::String)?;
+        let exchanges = exchanges.str()?;
+        let tradingsymbols = df.column("tradingsymbol")?.str()?;
+        for i in 0..df.height() {
+            let expected = match symbols.get(i).unwrap() {
+                "NSE:INFY" => (Some("NSE"), Some("INFY")),
+                "NFO:NIFTY24SEP25000CE" => (Some("NFO"), Some("NIFTY24SEP25000CE")),
+                "BSE:A:B" => (Some("BSE"), Some("A:B")),
+                "408065" => (None, Some("408065")),
+                other => panic!("unexpected symbol {other}"),
+            };
+            assert_eq!((exchanges.get(i), tradingsymbols.get(i)), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_float_columns() -> Result<(), Box<dyn Error>> {
+        let mut df = DataFrame::new(vec![
+            Series::new("last_price", &[1412.956, 0.004, f64::NAN]),
+            Series::new("change_pct", &[Some(-1.235), None, Some(2.0)]),
+            Series::new("volume", &[1u64, 2, 3]),
+        ])?;
+        let exact = df.clone();
+        round_float_columns(&mut df, 2, &["last_price", "change_pct"])?;
+
+        let prices: Vec<Option<f64>> = df.column("last_price")?.f64()?.into_iter().collect();
+        assert_eq!(prices[..2], [Some(1412.96), Some(0.0)]);
+        assert!(prices[2].unwrap().is_nan());
+        let changes: Vec<Option<f64>> = df.column("change_pct")?.f64()?.into_iter().collect();
+        assert_eq!(changes, [Some(-1.24), None, Some(2.0)]);
+        assert_eq!(exact.column("last_price")?.f64()?.get(0), Some(1412.956));
+
+        let err = round_float_columns(&mut df, 2, &["volume"]);
+        assert!(matches!(err, Err(PolarsError::SchemaMismatch(_))));
+        let err = round_float_columns(&mut df, 2, &["missing"]);
+        assert!(matches!(err, Err(PolarsError::ColumnNotFound(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_instrument_names() -> Result<(), Box<dyn Error>> {
+        let instruments = load_instruments_csv("kiteconnect-mocks/instruments.csv")?;
+        assert_eq!(instruments.height(), 4);
+        assert_eq!(
+            instruments.column("instrument_token")?.dtype(),
+            &DataType::UInt64
+        );
+        assert_eq!(instruments.column("expiry")?.null_count(), 1);
+
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let df = DataFrame::new(quote_series(&quotes))?;
+        let joined = join_instrument_names(df.clone(), &instruments)?;
+        assert_eq!(joined.height(), df.height());
+        assert_eq!(joined.width(), df.width() + 3);
+        assert!(joined.column("symbol")?.equals(df.column("symbol")?));
+
+        let symbols = joined.column("symbol")?.str()?;
+        let row = symbols
+            .into_iter()
+            .position(|s| s == Some("8960258"))
+            .unwrap();
+        assert_eq!(
+            joined.column("tradingsymbol")?.str()?.get(row),
+            Some("ACC21JUNFUT")
+        );
+        assert_eq!(joined.column("name")?.str()?.get(row), Some("ACC"));
+        let expiry = NaiveDate::from_ymd_opt(2021, 6, 24).unwrap();
+        assert_eq!(
+            joined.column("expiry")?.get(row)?,
+            AnyValue::Date((expiry - NaiveDate::default()).num_days() as i32)
+        );
+        assert_eq!(joined.column("name")?.null_count(), df.height() - 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_df_column_helpers() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let df = DataFrame::new(quote_series(&quotes))?;
+        let in_map_order = || quotes.instruments.iter();
+
+        let last_prices: Vec<f64> = in_map_order().map(|(_, q)| q.last_price).collect();
+        assert_eq!(df_column_f64(&df, "last_price")?, last_prices);
+        let volumes: Vec<u64> = in_map_order().map(|(_, q)| q.volume).collect();
+        assert_eq!(df_column_u64(&df, "volume")?, volumes);
+        let symbols: Vec<String> = in_map_order().map(|(s, _)| s.clone()).collect();
+        assert_eq!(df_column_str(&df, "symbol")?, symbols);
+
+        assert!(df_column_f64(&df, "volume").is_err());
+        assert!(df_column_u64(&df, "no_such_column").is_err());
+        let with_null = DataFrame::new(vec![Series::new("open", &[Some(1.0), None])])?;
+        let err = df_column_f64(&with_null, "open").unwrap_err();
+        assert!(err.to_string().contains("open: null at row 1"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_with_options() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+
+        let df = quote_to_polars_df_with_options(&quotes, &ConversionOptions::default())?;
+        assert!(df.equals_missing(&quote_to_polars_df_from_series_v2(quotes.clone())?));
+
+        let derived = vec![DerivedColumn::Spread, DerivedColumn::MidPrice];
+        let opts = ConversionOptions::new()
+            .parse_timestamps(true)
+            .include_depth(2)
+            .derived(derived.clone())
+            .null_on_zero_price(true);
+        let df = quote_to_polars_df_with_options(&quotes, &opts)?;
+        let base_width = quotes_schema().len();
+        assert_eq!(df.width(), base_width + 1 + 2 * 2 * 3 + derived.len());
+        let timestamps = quote_to_polars_df_dual_timestamp(&quotes)?;
+        assert!(df
+            .column("timestamp_parsed")?
+            .equals_missing(timestamps.column("timestamp_parsed")?));
+        let depth = quote_to_polars_df_with_depth(&quotes, 2, false)?;
+        for column in &depth.get_columns()[base_width..] {
+            assert!(df.column(column.name())?.equals_missing(column));
+        }
+        let zero_as_null = quote_to_polars_df_zero_as_null(&quotes)?;
+        assert!(df
+            .column("last_price")?
+            .equals_missing(zero_as_null.column("last_price")?));
+        let mut with_derived = DataFrame::new(quote_series(&quotes))?;
+        append_derived_columns(&mut with_derived, &quotes, &derived)?;
+        assert!(df
+            .column("mid_price")?
+            .equals_missing(with_derived.column("mid_price")?));
+
+        let opts = ConversionOptions::new()
+            .include_depth(1)
+            .signed_ints(true)
+            .sort_by("instrument_token");
+        let df = quote_to_polars_df_with_options(&quotes, &opts)?;
+        assert_eq!(df.column("volume")?.dtype(), &DataType::Int64);
+        assert_eq!(df.column("buy_quantity_1")?.dtype(), &DataType::Int64);
+        let tokens: Vec<Option<i64>> = df.column("instrument_token")?.i64()?.into_iter().collect();
+        assert!(tokens.windows(2).all(|w| w[0] <= w[1]));
+
+        let opts = ConversionOptions::new().sort_by("no_such_column");
+        assert!(quote_to_polars_df_with_options(&quotes, &opts).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_df_from_dir() -> Result<(), Box<dyn Error>> {
+        let dir = std::env::temp_dir().join("hello_test_quotes_df_from_dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir)?;
+        let raw = std::fs::read("kiteconnect-mocks/quotes.json")?;
+        std::fs::write(dir.join("0915.json"), &raw)?;
+        std::fs::write(dir.join("0916.json"), &raw)?;
+        std::fs::write(dir.join("0917.json"), b"{ truncated")?;
+        std::fs::write(dir.join("notes.txt"), b"not json")?;
+
+        let strict = quotes_df_from_dir(&dir, "*.json", true);
+        let lenient = quotes_df_from_dir_with_skipped(&dir, "*.json", false);
+        let plain = quotes_df_from_dir(&dir, "*.json", false);
+        let empty = quotes_df_from_dir_with_skipped(&dir, "*.parquet", true);
+        std::fs::remove_dir_all(&dir)?;
+
+        assert!(strict.unwrap_err().to_string().contains("0917.json"));
+        let (df, skipped) = lenient?;
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, dir.join("0917.json"));
+        assert!(skipped[0].1.downcast_ref::<serde_json::Error>().is_some());
+        assert_eq!(df.height(), 2 * 181);
+        assert_eq!(plain?.shape(), df.shape());
+        let sources = df.column("source_file")?.str()?;
+        assert_eq!(sources.get(0), Some("0915.json"));
+        assert_eq!(sources.get(df.height() - 1), Some("0916.json"));
+        let (empty, skipped) = empty?;
+        assert!(skipped.is_empty());
+        assert_eq!(empty.height(), 0);
+        assert_eq!(empty.schema(), df.schema());
+        Ok(())
+    }
+
+    #[test]
+    fn test_v2_strictness() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let strict = quote_to_polars_df_from_series_v2(quotes.clone())?;
+        let lenient = quote_to_polars_df_from_series_v2_with_strict(quotes, false)?;
+        assert!(strict.equals_missing(&lenient));
+
+        // The behaviour each mode relies on, on a column with a stray value.
+        let values = [AnyValue::Float64(1.5), AnyValue::String("n/a")];
+        assert!(Series::from_any_values_and_dtype("p", &values, &DataType::Float64, true).is_err());
+        let salvaged = Series::from_any_values_and_dtype("p", &values, &DataType::Float64, false)?;
+        assert_eq!(salvaged.f64()?.get(0), Some(1.5));
+        assert_eq!(salvaged.null_count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_columns() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let columns = QuotesColumns::from_quotes(&quotes);
+        assert_eq!(columns.len(), quotes.instruments.len());
+        for (i, symbol) in columns.symbols.iter().enumerate() {
+            let q = &quotes.instruments[symbol];
+            assert_eq!(columns.instrument_tokens[i], q.instrument_token);
+            assert_eq!(columns.closes[i], q.ohlc.as_ref().map(|o| o.close));
+        }
+
+        let df = columns.into_dataframe()?;
+        assert_eq!(df.schema(), quotes_schema());
+        let expected = quote_to_polars_df_from_series_v1(quotes)?;
+        let sorted = |df: DataFrame| df.sort(["symbol"], Default::default());
+        assert!(sorted(df)?.equals(&sorted(expected)?));
+        assert!(QuotesColumns::default().into_dataframe()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_lenient() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let (df, rejected) = quote_to_polars_df_lenient(&quotes);
+        assert_eq!(df.height(), quotes.instruments.len());
+        assert!(rejected.is_empty());
+
+        quotes
+            .instruments
+            .get_mut("8960002")
+            .unwrap()
+            .ohlc
+            .as_mut()
+            .unwrap()
+            .high = -1.0;
+        quotes
+            .instruments
+            .get_mut("8960258")
+            .unwrap()
+            .depth
+            .buy
+            .reverse();
+        quotes.instruments.get_mut("8960514").unwrap().last_price = f64::NAN;
+        let (df, rejected) = quote_to_polars_df_lenient(&quotes);
+        assert_eq!(df.height(), quotes.instruments.len() - 3);
+        assert_eq!(df.schema(), quotes_schema());
+        assert_eq!(
+            rejected,
+            vec![
+                ("8960002".to_owned(), "ohlc out of range".to_owned()),
+                ("8960258".to_owned(), "depth not well ordered".to_owned()),
+                ("8960514".to_owned(), "non-finite price".to_owned()),
+            ]
+        );
+        let symbols = df.column("symbol")?.str()?;
+        assert!(symbols
+            .into_iter()
+            .all(|s| rejected.iter().all(|(r, _)| Some(r.as_str()) != s)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tradable_columns() -> Result<(), Box<dyn Error>> {
+        let mut quotes = Quotes::default();
+        let full = QuotesData {
+            tradable: Some(true),
+            mode: Some("full".to_owned()),
+            ..QuotesData::default()
+        };
+        quotes.instruments.insert("NSE:INFY".to_owned(), full);
+        quotes
+            .instruments
+            .insert("NSE:OLD".to_owned(), QuotesData::default());
+
+        let df = quote_to_polars_df_with_tradable(&quotes)?;
+        assert_eq!(df.column("tradable")?.dtype(), &DataType::Boolean);
+        let symbols = df.column("symbol")?.str()?;
+        let tradable = df.column("tradable")?.bool()?;
+        let mode = df.column("mode")?.str()?;
+        for i in 0..df.height() {
+            let expected = match symbols.get(i).unwrap() {
+                "NSE:INFY" => (Some(true), Some("full")),
+                _ => (None, None),
+            };
+            assert_eq!((tradable.get(i), mode.get(i)), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ranges() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        quotes
+            .instruments
+            .get_mut("8960002")
+            .unwrap()
+            .ohlc
+            .as_mut()
+            .unwrap()
+            .close = 0.0;
+
+        let df = quote_to_polars_df_with_ranges(&quotes)?;
+        let symbols = df.column("symbol")?.str()?;
+        let ranges = df.column("day_range")?.f64()?;
+        let gaps = df.column("gap")?.f64()?;
+        for i in 0..df.height() {
+            let ohlc = &quotes.instruments[symbols.get(i).unwrap()]
+                .ohlc
+                .as_ref()
+                .unwrap();
+            assert_eq!(ranges.get(i), Some(ohlc.high - ohlc.low));
+            let gap = (ohlc.close != 0.0).then_some(ohlc.open - ohlc.close);
+            assert_eq!(gaps.get(i), gap);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_position() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        for (symbol, last_price, low, high) in [
+            ("8960002", 100.0, 100.0, 110.0),
+            ("8960258", 110.0, 100.0, 110.0),
+            ("8960514", 102.5, 100.0, 110.0),
+            ("8960770", 100.0, 100.0, 100.0),
+        ] {
+            let q = quotes.instruments.get_mut(symbol).unwrap();
+            q.last_price = last_price;
+            q.ohlc.as_mut().unwrap().low = low;
+            q.ohlc.as_mut().unwrap().high = high;
+        }
+
+        let df = quote_to_polars_df_with_range_position(&quotes)?;
+        assert_eq!(df.width(), quotes_schema().len() + 1);
+        let symbols = df.column("symbol")?.str()?;
+        let positions = df.column("range_position")?.f64()?;
+        let position_of = |symbol| {
+            let row = symbols.into_iter().position(|s| s == Some(symbol)).unwrap();
+            positions.get(row)
+        };
+        assert_eq!(position_of("8960002"), Some(0.0));
+        assert_eq!(position_of("8960258"), Some(1.0));
+        assert_eq!(position_of("8960514"), Some(0.25));
+        assert_eq!(position_of("8960770"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_tokens() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let unsigned = DataFrame::new(quote_series(&quotes))?;
+        let signed = quote_to_polars_df_signed_tokens(&quotes)?;
+
+        for (u, s) in unsigned.get_columns().iter().zip(signed.get_columns()) {
+            assert_eq!(u.name(), s.name());
+            if u.dtype() == &DataType::UInt64 {
+                assert_eq!(s.dtype(), &DataType::Int64);
+                assert!(s.equals(&u.cast(&DataType::Int64)?));
+            } else {
+                assert!(s.equals_missing(u));
+            }
+        }
+        assert_eq!(signed.column("volume")?.dtype(), &DataType::Int64);
+        assert_eq!(signed.column("last_price")?.dtype(), &DataType::Float64);
+
+        let mut huge = Quotes::default();
+        let q = QuotesData {
+            volume: u64::MAX,
+            ..QuotesData::default()
+        };
+        huge.instruments.insert("NSE:INFY".to_owned(), q);
+        assert!(quote_to_polars_df_signed_tokens(&huge).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_capture_time() -> Result<(), Box<dyn Error>> {
+        let path = "kiteconnect-mocks/quotes.json";
+        let df = quotes_df_from_file_with_capture_time(path)?;
+        assert_eq!(df.height(), 181);
+        let captured_at = df.column("captured_at")?;
+        assert_eq!(
+            captured_at.dtype(),
+            &DataType::Datetime(TimeUnit::Milliseconds, None)
+        );
+        assert_eq!(captured_at.n_unique()?, 1);
+        let modified = std::fs::metadata(path)?.modified()?;
+        let expected = chrono::DateTime::<chrono::Utc>::from(modified).timestamp_millis();
+        assert_eq!(captured_at.datetime()?.get(0), Some(expected));
+        Ok(())
+    }
+
+    // Compares `df` against a committed Parquet file. Run the tests with
+    // `UPDATE_GOLDEN=1` to rewrite the file from `df` after an intended change.
+    fn assert_df_matches_golden(df: &DataFrame, golden_path: &str) {
+        use polars::prelude::ParquetReader;
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let mut file = File::create(golden_path).unwrap();
+            ParquetWriter::new(&mut file)
+                .finish(&mut df.clone())
+                .unwrap();
+            return;
+        }
+        let file = File::open(golden_path)
+            .unwrap_or_else(|e| panic!("{golden_path}: {e}; run with UPDATE_GOLDEN=1"));
+        let golden = ParquetReader::new(file).finish().unwrap();
+        assert_eq!(
+            df.schema(),
+            golden.schema(),
+            "schema differs from {golden_path}"
+        );
+        assert!(
+            df.equals_missing(&golden),
+            "values differ from {golden_path}:\n{df}\n{golden}"
+        );
+    }
+
+    #[test]
+    fn test_canonical_conversion_matches_golden() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let df = quote_to_polars_df_from_series_v2(quotes)?.sort(["symbol"], Default::default())?;
+        assert_df_matches_golden(&df, "kiteconnect-mocks/golden/quotes.parquet");
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_quotes_file_to_parquet() -> Result<(), Box<dyn Error>> {
+        use polars::prelude::ParquetReader;
+
+        let input = std::path::PathBuf::from("kiteconnect-mocks/quotes.json");
+        let output = std::env::temp_dir().join("hello_test_stream_quotes.parquet");
+        let written = stream_quotes_file_to_parquet(&input, &output, 50)?;
+
+        let quotes: Quotes = serde_json::from_reader(read_json_from_file(&input)?)?;
+        assert_eq!(written, quotes.instruments.len());
+        let sorted = |df: DataFrame| df.sort(["symbol"], Default::default());
+        let df = sorted(ParquetReader::new(File::open(&output)?).finish()?)?;
+        std::fs::remove_file(&output)?;
+        assert_eq!(df.schema(), quotes_schema());
+        assert!(df.equals(&sorted(DataFrame::new(quote_series(&quotes))?)?));
+
+        assert!(stream_quotes_file_to_parquet(&input, &output, 0).is_err());
+
+        // A payload that fails mid-stream leaves no output file behind.
+        let broken = std::env::temp_dir().join("hello_test_stream_quotes_broken.json");
+        let valid = std::fs::read_to_string(&input)?;
+        let payload = format!(
+            "{}, \"BROKEN\": {{\"instrument_token\": \"bad\"}}}}",
+            valid.trim_end().trim_end_matches('}')
+        );
+        std::fs::write(&broken, payload)?;
+        assert!(stream_quotes_file_to_parquet(&broken, &output, 1).is_err());
+        std::fs::remove_file(&broken)?;
+        assert!(!output.exists());
+
+        // Input and output may be different path types.
+        let written = stream_quotes_file_to_parquet("kiteconnect-mocks/quotes.json", &output, 50)?;
+        std::fs::remove_file(&output)?;
+        assert_eq!(written, quotes.instruments.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_dtypes() -> Result<(), Box<dyn Error>> {
+        let dtypes = Quotes::column_dtypes();
+        assert_eq!(dtypes.len(), 20);
+        assert_eq!(dtypes[0], ("symbol".to_owned(), DataType::String));
+        assert_eq!(dtypes[1], ("instrument_token".to_owned(), DataType::UInt64));
+
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let df = DataFrame::new(quote_series(&quotes))?;
+        let actual: Vec<(String, DataType)> = df
+            .get_columns()
+            .iter()
+            .map(|s| (s.name().to_string(), s.dtype().clone()))
+            .collect();
+        assert_eq!(actual, dtypes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_with_rowhash() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let hashes = |quotes: &Quotes| -> Result<HashMap<String, u64>, PolarsError> {
+            let df = quote_to_polars_df_with_rowhash(quotes)?;
+            let symbols = df.column("symbol")?.str()?.clone();
+            let row_hashes = df.column("row_hash")?.u64()?.clone();
+            Ok(symbols
+                .into_iter()
+                .zip(&row_hashes)
+                .map(|(s, h)| (s.unwrap().to_owned(), h.unwrap()))
+                .collect())
+        };
+
+        let before = hashes(&quotes)?;
+        assert_eq!(before.len(), quotes.instruments.len());
+        let rebuilt: Quotes = quotes.instruments.clone().into_iter().collect();
+        assert_eq!(hashes(&rebuilt)?, before);
+
+        let mut changed = quotes.clone();
+        changed
+            .instruments
+            .get_mut("8960002")
+            .unwrap()
+            .ohlc
+            .as_mut()
+            .unwrap()
+            .high += 0.05;
+        changed
+            .instruments
+            .get_mut("8960258")
+            .unwrap()
+            .depth
+            .buy
+            .clear();
+        let after = hashes(&changed)?;
+        for (symbol, hash) in &before {
+            match symbol.as_str() {
+                "8960002" => assert_ne!(after[symbol], *hash),
+                _ => assert_eq!(after[symbol], *hash),
+            }
+        }
+
+        // Pinned so an accidental change to the hashed fields shows up.
+        let q = QuotesData {
+            instrument_token: 408065,
+            last_price: 1412.95,
+            ohlc: Some(OhlcInner::default()),
+            ..QuotesData::default()
+        };
+        assert_eq!(row_hash("NSE:INFY", &q), 3306017854610469699);
+        assert_ne!(row_hash("NSE:INFY", &q), row_hash("NSE:TCS", &q));
+        let nan_ohlc = QuotesData {
+            ohlc: Some(OhlcInner {
+                open: f64::NAN,
+                high: f64::NAN,
+                low: f64::NAN,
+                close: f64::NAN,
+            }),
+            ..q.clone()
+        };
+        let pre_open = QuotesData { ohlc: None, ..q };
+        assert_ne!(row_hash("NSE:INFY", &pre_open), 3306017854610469699);
+        assert_ne!(
+            row_hash("NSE:INFY", &pre_open),
+            row_hash("NSE:INFY", &nan_ohlc)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_numeric_only() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let df = quote_to_polars_df_numeric_only(&quotes)?;
+        assert_eq!(df.shape(), (quotes.instruments.len(), 17));
+        assert!(df.dtypes().iter().all(|dtype| dtype.is_numeric()));
+
+        let full = DataFrame::new(quote_series(&quotes))?;
+        let expected = full.drop_many(&["symbol", "timestamp", "last_trade_time"]);
+        assert!(df.equals(&expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_zero_as_null() -> Result<(), Box<dyn Error>> {
+        let mut quotes = Quotes::default();
+        quotes.instruments.insert(
+            "NSE:IPO".to_owned(),
+            QuotesData {
+                instrument_token: 1,
+                upper_circuit_limit: 120.0,
+                ..QuotesData::default()
+            },
+        );
+        quotes.instruments.insert(
+            "NSE:INFY".to_owned(),
+            QuotesData {
+                instrument_token: 2,
+                last_price: 1412.95,
+                ohlc: Some(OhlcInner {
+                    open: 1400.0,
+                    high: 1420.0,
+                    low: 1395.0,
+                    close: 1410.0,
+                }),
+                ..QuotesData::default()
+            },
+        );
+        let df = quote_to_polars_df_zero_as_null(&quotes)?
+            .sort(["instrument_token"], Default::default())?;
+        assert_eq!(df.schema(), quotes_schema());
+
+        for name in ["last_price", "open", "high", "low", "close"] {
+            assert_eq!(df.column(name)?.f64()?.get(0), None, "{name}");
+            assert!(df.column(name)?.f64()?.get(1).is_some(), "{name}");
+        }
+        assert_eq!(df.column("average_price")?.null_count(), 2);
+        assert_eq!(df.column("net_change")?.null_count(), 0);
+        assert_eq!(df.column("lower_circuit_limit")?.f64()?.get(0), Some(0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_df_from_slice() -> Result<(), Box<dyn Error>> {
+        let bytes = std::fs::read("kiteconnect-mocks/quotes.json")?;
+        let df = quotes_df_from_slice(&bytes)?;
+        assert_eq!(df.schema(), quotes_schema());
+        assert_eq!(df.height(), Quotes::from_slice(&bytes)?.instruments.len());
+        assert!(quotes_df_from_slice(b"not json").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_with_depth() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        quotes
+            .instruments
+            .get_mut("8960002")
+            .unwrap()
+            .depth
+            .sell
+            .truncate(1);
+
+        let df = quote_to_polars_df_with_depth(&quotes, 2, false)?;
+        assert_eq!(df.width(), 20 + 2 * 2 * 3);
+        assert_eq!(df.column("buy_quantity_1")?.dtype(), &DataType::UInt64);
+        let symbols = df.column("symbol")?.str()?;
+        let row = symbols
+            .into_iter()
+            .position(|s| s == Some("8960002"))
+            .unwrap();
+        let q = &quotes.instruments["8960002"];
+        assert_eq!(
+            df.column("sell_price_1")?.f64()?.get(row),
+            Some(q.depth.sell[0].price)
+        );
+        assert_eq!(df.column("sell_price_2")?.f64()?.get(row), None);
+        assert_eq!(df.column("sell_orders_2")?.u64()?.get(row), None);
+
+        let narrow = quote_to_polars_df_with_depth(&quotes, 2, true)?;
+        assert_eq!(narrow.column("buy_quantity_1")?.dtype(), &DataType::UInt32);
+        assert_eq!(narrow.column("sell_orders_2")?.dtype(), &DataType::UInt32);
+        assert_eq!(
+            narrow.column("buy_quantity_1")?.cast(&DataType::UInt64)?,
+            df.column("buy_quantity_1")?.clone()
+        );
+
+        quotes.instruments.get_mut("8960002").unwrap().depth.buy[1].quantity =
+            u64::from(u32::MAX) + 1;
+        let err = quote_to_polars_df_with_depth(&quotes, 2, true).unwrap_err();
+        assert!(err.to_string().contains("8960002: buy_quantity_2"));
+        assert!(quote_to_polars_df_with_depth(&quotes, 2, false).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_with_twenty_level_depth() -> Result<(), Box<dyn Error>> {
+        let level = |i: usize| OrderDepth {
+            price: 100.0 + i as f64,
+            quantity: 10 * i as u64,
+            orders: i as u64,
+        };
+        let deep = QuotesData {
+            depth: Depth {
+                buy: (1..=20).map(level).collect(),
+                sell: (1..=20).map(level).collect(),
+            },
+            ..QuotesData::default()
+        };
+        let quotes: Quotes = [("NSE:INFY".to_owned(), deep)].into_iter().collect();
+
+        let df = quote_to_polars_df_with_depth(&quotes, 20, false)?;
+        assert_eq!(df.width(), 20 + 2 * 20 * 3);
+        assert_eq!(df.column("sell_price_20")?.f64()?.get(0), Some(120.0));
+        assert_eq!(df.column("buy_orders_20")?.u64()?.get(0), Some(20));
+
+        let df = quote_to_polars_df_with_depth(&quotes, DEFAULT_DEPTH_LEVELS, false)?;
+        assert_eq!(df.width(), 20 + 2 * 5 * 3);
+        assert!(df.column("buy_price_6").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_with_liquidity() -> Result<(), Box<dyn Error>> {
+        let level = |price, quantity| OrderDepth {
+            price,
+            quantity,
+            orders: 1,
+        };
+        let quote = |volume, bid, ask, quantity| QuotesData {
+            volume,
+            depth: Depth {
+                buy: vec![level(bid, quantity)],
+                sell: vec![level(ask, quantity)],
+            },
+            ..QuotesData::default()
+        };
+        let quotes = Quotes {
+            instruments: HashMap::from([
+                ("DEEP".to_owned(), quote(1000, 99.0, 101.0, 500)),
+                ("THIN".to_owned(), quote(500, 99.0, 100.0, 50)),
+                ("DEAD".to_owned(), quote(0, 0.0, 0.0, 0)),
+            ]),
+        };
+        let scores = |weights| -> Result<HashMap<String, f64>, PolarsError> {
+            let df = quote_to_polars_df_with_liquidity(&quotes, weights)?;
+            let symbols = df.column("symbol")?.str()?.clone();
+            let scores = df.column("liquidity_score")?.f64()?.clone();
+            Ok(symbols
+                .into_iter()
+                .zip(&scores)
+                .map(|(s, v)| (s.unwrap().to_owned(), v.unwrap()))
+                .collect())
+        };
+
+        let all = scores(LiquidityWeights::default())?;
+        assert_eq!(all["DEEP"], 1.0 + 1.0 + 0.5);
+        assert_eq!(all["THIN"], 0.5 + 0.1 + 1.0);
+        assert_eq!(all["DEAD"], 0.0);
+
+        let volume_only = scores(LiquidityWeights {
+            volume: 2.0,
+            depth: 0.0,
+            inverse_spread: 0.0,
+        })?;
+        assert_eq!(volume_only["THIN"], 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_constant_columns() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut df = DataFrame::new(quote_series(&quotes))?;
+        with_constant_columns(
+            &mut df,
+            &[
+                ("run_id", AnyValue::UInt64(42)),
+                ("source", AnyValue::String("kite")),
+                ("note", AnyValue::Null),
+            ],
+        )?;
+        assert_eq!(df.shape(), (quotes.instruments.len(), 23));
+        let run_ids = df.column("run_id")?.u64()?;
+        assert!(run_ids.into_iter().all(|v| v == Some(42)));
+        let sources = df.column("source")?.str()?;
+        assert!(sources.into_iter().all(|v| v == Some("kite")));
+        assert_eq!(df.column("note")?.null_count(), df.height());
+
+        let mut empty = DataFrame::new(quote_series(&Quotes::default()))?;
+        with_constant_columns(&mut empty, &[("run_id", AnyValue::UInt64(42))])?;
+        assert_eq!(empty.shape(), (0, 21));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_with_turnover() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let q = &quotes.instruments["8960002"];
+        let turnover_of = |df: &DataFrame| -> Result<Option<f64>, PolarsError> {
+            let row = df
+                .column("symbol")?
+                .str()?
+                .into_iter()
+                .position(|s| s == Some("8960002"))
+                .unwrap();
+            Ok(df.column("turnover")?.f64()?.get(row))
+        };
+
+        let df = quote_to_polars_df_with_turnover(&quotes)?;
+        assert_eq!(df.width(), 21);
+        // 25361.73 * 5102800
+        assert_eq!(turnover_of(&df)?, Some(129_415_835_844.0));
+
+        let df = quote_to_polars_df_with_turnover_by(&quotes, TurnoverPrice::LastPrice)?;
+        assert_eq!(turnover_of(&df)?, Some(q.last_price * q.volume as f64));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_float_volume() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+
+        let df = quote_to_polars_df_float_volume(&quotes, false)?;
+        assert_eq!(df.schema(), quotes_schema());
+
+        let df = quote_to_polars_df_float_volume(&quotes, true)?;
+        for name in ["last_quantity", "buy_quantity", "sell_quantity", "volume"] {
+            assert_eq!(df.column(name)?.dtype(), &DataType::Float64, "{name}");
+        }
+        assert_eq!(df.column("oi")?.dtype(), &DataType::UInt64);
+        assert_eq!(df.column("instrument_token")?.dtype(), &DataType::UInt64);
+        let standard = DataFrame::new(quote_series(&quotes))?;
+        assert!(df
+            .column("volume")?
+            .equals(&standard.column("volume")?.cast(&DataType::Float64)?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_to_polars_df_with_dominant_side() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        quotes.instruments.get_mut("8960002").unwrap().depth = Depth::default();
+
+        let df = quote_to_polars_df_with_dominant_side(&quotes)?;
+        let sides = df.column("dominant_side")?;
+        assert!(matches!(sides.dtype(), DataType::Categorical(_, _)));
+        let sides = sides.cast(&DataType::String)?;
+        let symbols = df.column("symbol")?.str()?;
+        for (symbol, side) in symbols.into_iter().zip(sides.str()?) {
+            let expected = quotes.instruments[symbol.unwrap()]
+                .dominant_side()
+                .map(|side| side.as_str());
+            assert_eq!(side, expected);
+            if symbol == Some("8960002") {
+                assert_eq!(side, None);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_sinks() -> Result<(), Box<dyn Error>> {
+        use polars::prelude::{CsvReader, ParquetReader};
+
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let expected = DataFrame::new(quote_series(&quotes))?;
+
+        let mut csv = CsvSink::new(Vec::new());
+        quotes.convert_and_write(&mut csv)?;
+        quotes.convert_and_write(&mut csv)?;
+        let csv = CsvReader::new(Cursor::new(csv.into_inner())).finish()?;
+        assert_eq!(csv.height(), 2 * expected.height());
+        assert_eq!(csv.get_column_names(), expected.get_column_names());
+
+        let mut parquet = ParquetSink::new(Vec::new());
+        quotes.convert_and_write(&mut parquet)?;
+        assert!(matches!(
+            quotes.convert_and_write(&mut parquet),
+            Err(PolarsError::InvalidOperation(_))
+        ));
+        let parquet = ParquetReader::new(Cursor::new(parquet.into_inner())).finish()?;
+        assert!(parquet.equals(&expected));
+
+        let mut ipc = IpcSink::new(Vec::new());
+        quotes.convert_and_write(&mut ipc)?;
+        assert!(matches!(
+            quotes.convert_and_write(&mut ipc),
+            Err(PolarsError::InvalidOperation(_))
+        ));
+        let ipc = IpcReader::new(Cursor::new(ipc.into_inner())).finish()?;
+        assert!(ipc.equals(&expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_microprice() -> Result<(), Box<dyn Error>> {
+        let level = |price, quantity| OrderDepth {
+            price,
+            quantity,
+            orders: 1,
+        };
+        let book = |buy: Vec<OrderDepth>, sell: Vec<OrderDepth>| QuotesData {
+            depth: Depth { buy, sell },
+            ..QuotesData::default()
+        };
+        let mut quotes = Quotes::default();
+        quotes.instruments.insert(
+            "NSE:TWO".to_owned(),
+            book(vec![level(99.0, 300)], vec![level(101.0, 100)]),
+        );
+        quotes.instruments.insert(
+            "NSE:ONE".to_owned(),
+            book(vec![level(99.0, 300)], vec![level(0.0, 0)]),
+        );
+
+        let df = quote_to_polars_df_with_microprice(&quotes)?;
+        let symbols = df.column("symbol")?.str()?;
+        let mid = df.column("mid_price")?.f64()?;
+        let micro = df.column("micro_price")?.f64()?;
+        for i in 0..df.height() {
+            let expected = match symbols.get(i).unwrap() {
+                // Heavier bids pull the micro-price towards the ask.
+                "NSE:TWO" => (Some(100.0), Some((99.0 * 100.0 + 101.0 * 300.0) / 400.0)),
+                _ => (None, None),
+            };
+            assert_eq!((mid.get(i), micro.get(i)), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_depthless_instrument() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut value = serde_json::to_value(&quotes.instruments["8960002"])?;
+        value.as_object_mut().unwrap().remove("depth");
+        quotes
+            .instruments
+            .insert("8960002".to_owned(), serde_json::from_value(value)?);
+
+        let mut df = DataFrame::new(quote_series(&quotes))?;
+        let depth_based = [
+            DerivedColumn::Spread,
+            DerivedColumn::MidPrice,
+            DerivedColumn::MicroPrice,
+        ];
+        append_derived_columns(&mut df, &quotes, &depth_based)?;
+        let symbols = df.column("symbol")?.str()?;
+        let row = (0..df.height())
+            .find(|&i| symbols.get(i) == Some("8960002"))
+            .unwrap();
+        for column in depth_based {
+            let values = df.column(column.name())?.f64()?;
+            assert_eq!(values.get(row), None);
+            assert_eq!(values.null_count(), 1);
+        }
+        let (_, rejected) = quote_to_polars_df_lenient(&quotes);
+        assert!(rejected.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_round_trip() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut df = quote_to_polars_df_from_series_v1(quotes)?;
+
+        let path = std::env::temp_dir().join("hello_test_csv_round_trip.csv");
+        write_df_to_csv(&mut df, &path)?;
+        let read_back = quote_df_from_csv(&path)?;
+        assert_eq!(read_back.schema(), quotes_schema());
+        assert!(df.equals(&read_back));
+
+        write_df_to_csv(&mut df.drop("volume")?, &path)?;
+        let missing = quote_df_from_csv(&path);
+        std::fs::remove_file(&path)?;
+        assert!(matches!(missing, Err(PolarsError::ColumnNotFound(_))));
+        Ok(())
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_cached_converter() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut converter = CachedConverter::new();
+
+        let first = converter.convert(&quotes)?;
+        let rebuilt: Quotes = quotes.instruments.clone().into_iter().collect();
+        assert_eq!(
+            CachedConverter::content_hash(&quotes),
+            CachedConverter::content_hash(&rebuilt)
+        );
+        assert!(converter.convert(&rebuilt)?.equals_missing(&first));
+
+        let mut changed = quotes.clone();
+        let symbol = changed.instruments.keys().next().unwrap().clone();
+        changed.instruments.get_mut(&symbol).unwrap().last_price += 1.0;
+        assert_ne!(
+            CachedConverter::content_hash(&quotes),
+            CachedConverter::content_hash(&changed)
+        );
+        let df = converter.convert(&changed)?;
+        let row = df
+            .column("symbol")?
+            .str()?
+            .into_iter()
+            .position(|s| s == Some(symbol.as_str()))
+            .unwrap();
+        let last_price = df.column("last_price")?.f64()?.get(row);
+        assert_eq!(last_price, Some(changed.instruments[&symbol].last_price));
+
+        // A matching version token wins even though the content differs.
+        let versioned = converter.convert_versioned(7, &quotes)?;
+        assert!(converter
+            .convert_versioned(7, &changed)?
+            .equals_missing(&versioned));
+        Ok(())
+    }
+
+    fn order_depth_strategy() -> impl Strategy<Value = OrderDepth> {
+        (0.0..1e6f64, any::<u32>(), any::<u16>()).prop_map(|(price, quantity, orders)| OrderDepth {
+            price,
+            quantity: quantity.into(),
+            orders: orders.into(),
+        })
+    }
+
+    fn quotes_data_strategy() -> impl Strategy<Value = QuotesData> {
+        let timestamp = "20[0-9]{2}-[01][0-9]-[0-3][0-9] [0-2][0-9]:[0-5][0-9]:[0-5][0-9]";
+        (
+            (
+                any::<u64>(),
+                timestamp,
+                timestamp,
+                0.0..1e6f64,
+                any::<u64>(),
+                any::<u64>(),
+                any::<u64>(),
+                any::<u64>(),
+                0.0..1e6f64,
+            ),
+            (
+                any::<u64>(),
+                any::<u64>(),
+                any::<u64>(),
+                -1e4..1e4f64,
+                0.0..1e6f64,
+                0.0..1e6f64,
+                prop::array::uniform4(0.0..1e6f64),
+                prop::collection::vec(order_depth_strategy(), 0..=5),
+                prop::collection::vec(order_depth_strategy(), 0..=5),
+            ),
+        )
+            .prop_map(
+                |(
+                    (
+                        instrument_token,
+                        timestamp,
+                        last_trade_time,
+                        last_price,
+                        last_quantity,
+                        buy_quantity,
+                        sell_quantity,
+                        volume,
+                        average_price,
+                    ),
+                    (
+                        oi,
+                        oi_day_high,
+                        oi_day_low,
+                        net_change,
+                        lower_circuit_limit,
+                        upper_circuit_limit,
+                        [open, high, low, close],
+                        buy,
+                        sell,
+                    ),
+                )| QuotesData {
+                    instrument_token,
+                    timestamp,
+                    last_trade_time,
+                    last_price,
+                    last_quantity,
+                    buy_quantity,
+                    sell_quantity,
+                    volume,
+                    average_price,
+                    oi,
+                    oi_day_high,
+                    oi_day_low,
+                    net_change,
+                    lower_circuit_limit,
+                    upper_circuit_limit,
+                    ohlc: Some(OhlcInner {
+                        open,
+                        high,
+                        low,
+                        close,
+                    }),
+                    depth: Depth { buy, sell },
+                    tradable: None,
+                    mode: None,
+                },
+            )
+    }
+
+    fn quotes_strategy() -> impl Strategy<Value = Quotes> {
+        prop::collection::hash_map("[A-Z]{3}:[A-Z0-9]{1,12}", quotes_data_strategy(), 0..40)
+            .prop_map(|instruments| Quotes { instruments })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_conversion_variants_agree(quotes in quotes_strategy()) {
+            let sorted = |df: DataFrame| df.sort(["symbol"], Default::default()).unwrap();
+            let expected = sorted(quote_to_polars_df_from_series_raghu(quotes.clone()).unwrap());
+            let variants = [
+                quote_to_polars_df_from_series_v0(quotes.clone()).unwrap(),
+                quote_to_polars_df_from_series_v1(quotes.clone()).unwrap(),
+                quote_to_polars_df_from_series_v2(quotes.clone()).unwrap(),
+                quote_to_polars_df_from_series_v3(quotes.clone()).unwrap(),
+                quote_to_polars_df_from_rows_cols(quotes.clone()).unwrap(),
+            ];
+            prop_assert_eq!(expected.height(), quotes.instruments.len());
+            for df in variants {
+                let df = sorted(df);
+                prop_assert_eq!(df.schema(), expected.schema());
+                prop_assert!(df.equals(&expected));
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::types::*;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+const KITE_API_ROOT: &str = "https://api.kite.trade";
+
+// Kite rejects `/quote` requests for more instruments than this.
+pub const MAX_QUOTE_INSTRUMENTS: usize = 500;
+
+// A non-success `{status, message, error_type}` envelope from Kite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KiteApiError {
+    pub status: Status,
+    pub error_type: Option<Exception>,
+    pub message: String,
+}
+
+impl fmt::Display for KiteApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error_type {
+            Some(error_type) => write!(f, "{error_type}: {}", self.message),
+            None => write!(f, "Kite request failed: {}", self.message),
+        }
+    }
+}
+
+impl Error for KiteApiError {}
+
+// Fetches full quotes for `instruments` (`EXCHANGE:TRADINGSYMBOL` keys) from
+// Kite's `/quote` endpoint.
+pub fn fetch_quotes(
+    api_key: &str,
+    access_token: &str,
+    instruments: &[&str],
+) -> Result<Quotes, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::new();
+    fetch_quotes_with(&client, api_key, access_token, instruments)
+}
+
+// Requests `instruments` in chunks of `MAX_QUOTE_INSTRUMENTS`, sleeping
+// `delay` between requests to stay under the rate limit, and merges the
+// results. Fetching stops at the first failed chunk; the error carries the
+// quotes fetched so far along with the failed and unattempted instruments.
+pub fn fetch_quotes_batched(
+    api_key: &str,
+    access_token: &str,
+    instruments: &[&str],
+    delay: Duration,
+) -> Result<Quotes, PartialFetchError> {
+    let client = reqwest::blocking::Client::new();
+    fetch_in_chunks(instruments, MAX_QUOTE_INSTRUMENTS, delay, |chunk| {
+        fetch_quotes_with(&client, api_key, access_token, chunk)
+    })
+}
+
+#[derive(Debug)]
+pub struct PartialFetchError {
+    pub fetched: Quotes,
+    pub failed_chunk: Vec<String>,
+    pub remaining: Vec<String>,
+    pub source: Box<dyn Error>,
+}
+
+impl fmt::Display for PartialFetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fetching {} instruments failed after {} succeeded: {}",
+            self.failed_chunk.len(),
+            self.fetched.instruments.len(),
+            self.source
+        )
+    }
+}
+
+impl Error for PartialFetchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+fn fetch_in_chunks<F>(
+    instruments: &[&str],
+    chunk_size: usize,
+    delay: Duration,
+    mut fetch: F,
+) -> Result<Quotes, PartialFetchError>
+where
+    F: FnMut(&[&str]) -> Result<Quotes, Box<dyn Error>>,
+{
+    let mut fetched = Quotes::default();
+    for (i, chunk) in instruments.chunks(chunk_size).enumerate() {
+        if i > 0 && !delay.is_zero() {
+            thread::sleep(delay);
+        }
+        match fetch(chunk) {
+            Ok(quotes) => {
+                fetched.merge(quotes, MergePolicy::Overwrite);
+            }
+            Err(source) => {
+                let to_owned = |symbols: &[&str]| symbols.iter().map(|&s| s.to_owned()).collect();
+                return Err(PartialFetchError {
+                    fetched,
+                    failed_chunk: to_owned(chunk),
+                    remaining: to_owned(&instruments[i * chunk_size + chunk.len()..]),
+                    source,
+                });
+            }
+        }
+    }
+    Ok(fetched)
+}
+
+fn fetch_quotes_with(
+    client: &reqwest::blocking::Client,
+    api_key: &str,
+    access_token: &str,
+    instruments: &[&str],
+) -> Result<Quotes, Box<dyn Error>> {
+    let query: Vec<(&str, &str)> = instruments.iter().map(|&i| ("i", i)).collect();
+    let body = client
+        .get(format!("{KITE_API_ROOT}/quote"))
+        .header("X-Kite-Version", "3")
+        .header("Authorization", format!("token {api_key}:{access_token}"))
+        .query(&query)
+        .send()?
+        .bytes()?;
+    quotes_from_envelope(&body)
+}
+
+// Kite reports failures in the body as well as the HTTP status, so the
+// envelope is checked rather than the status code.
+fn quotes_from_envelope(body: &[u8]) -> Result<Quotes, Box<dyn Error>> {
+    #[derive(Deserialize)]
+    struct Envelope {
+        status: Status,
+        #[serde(default)]
+        data: Option<Quotes>,
+        #[serde(default)]
+        message: Option<String>,
+        #[serde(default)]
+        error_type: Option<Exception>,
+    }
+
+    let envelope: Envelope = serde_json::from_slice(body)?;
+    match (envelope.status, envelope.data) {
+        (Status::Success, Some(data)) => Ok(data),
+        (Status::Success, None) => Ok(Quotes::default()),
+        (status, _) => Err(Box::new(KiteApiError {
+            status,
+            error_type: envelope.error_type,
+            message: envelope.message.unwrap_or_default(),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotes_from_envelope() -> Result<(), Box<dyn Error>> {
+        let data = std::fs::read_to_string("kiteconnect-mocks/quotes.json")?;
+        let body = format!(r#"{{"status":"success","data":{data}}}"#);
+        assert_eq!(
+            quotes_from_envelope(body.as_bytes())?,
+            Quotes::from_slice(data.as_bytes())?
+        );
+
+        let body = r#"{"status":"error","message":"Invalid `api_key` or `access_token`.","error_type":"TokenException"}"#;
+        let err = quotes_from_envelope(body.as_bytes()).unwrap_err();
+        let err = err.downcast_ref::<KiteApiError>().unwrap();
+        assert_eq!(err.status, Status::Error);
+        assert_eq!(err.error_type, Some(Exception::TokenException));
+        assert_eq!(
+            err.to_string(),
+            "TokenException: Invalid `api_key` or `access_token`."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_in_chunks() {
+        let symbols: Vec<String> = (0..7).map(|i| format!("NSE:SYM{i}")).collect();
+        let instruments: Vec<&str> = symbols.iter().map(String::as_str).collect();
+        let fake_fetch = |chunk: &[&str]| -> Result<Quotes, Box<dyn Error>> {
+            if chunk.contains(&"NSE:SYM4") {
+                return Err("rate limited".into());
+            }
+            Ok(chunk
+                .iter()
+                .map(|&s| (s.to_owned(), QuotesData::default()))
+                .collect())
+        };
+
+        let mut sizes = Vec::new();
+        let quotes = fetch_in_chunks(&instruments[..4], 3, Duration::ZERO, |chunk| {
+            sizes.push(chunk.len());
+            fake_fetch(chunk)
+        })
+        .unwrap();
+        assert_eq!(sizes, [3, 1]);
+        assert_eq!(quotes.instruments.len(), 4);
+
+        let err = fetch_in_chunks(&instruments, 3, Duration::ZERO, fake_fetch).unwrap_err();
+        assert_eq!(err.fetched.instruments.len(), 3);
+        assert_eq!(err.failed_chunk, ["NSE:SYM3", "NSE:SYM4", "NSE:SYM5"]);
+        assert_eq!(err.remaining, ["NSE:SYM6"]);
+
+        let err = fetch_in_chunks(&instruments[..5], 3, Duration::ZERO, fake_fetch).unwrap_err();
+        assert_eq!(err.failed_chunk, ["NSE:SYM3", "NSE:SYM4"]);
+        assert!(err.remaining.is_empty());
+        assert_eq!(err.source.to_string(), "rate limited");
+    }
+}
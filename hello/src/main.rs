@@ -4,20 +4,38 @@ use hello::{
     quote_to_polars_df_from_series_v2, quote_to_polars_df_from_series_v3,
 };
 use hello::{read_json_from_file, Quotes};
+use polars::prelude::{DataFrame, PolarsError};
+use std::error::Error;
+use std::process::ExitCode;
 
-fn main() {
-    let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json").unwrap();
-    let quotes: Quotes = serde_json::from_reader(jsonfile).unwrap();
-    let df = quote_to_polars_df_from_series_raghu(quotes.clone()).unwrap();
-    println!("{:#?}", &df);
-    let df = quote_to_polars_df_from_series_v0(quotes.clone()).unwrap();
-    println!("{:#?}", &df);
-    let df = quote_to_polars_df_from_series_v1(quotes.clone()).unwrap();
-    println!("{:#?}", &df);
-    let df = quote_to_polars_df_from_series_v2(quotes.clone()).unwrap();
-    println!("{:#?}", &df);
-    let df = quote_to_polars_df_from_series_v3(quotes.clone()).unwrap();
-    println!("{:#?}", &df);
-    let df = quote_to_polars_df_from_rows_cols(quotes.clone()).unwrap();
-    println!("{:#?}", &df);
+// Converts the bundled mock quotes with each implementation and prints the
+// frames. A failing conversion is reported by name.
+fn run_demo() -> Result<(), Box<dyn Error>> {
+    type Conversion = fn(Quotes) -> Result<DataFrame, PolarsError>;
+    let conversions: [(&str, Conversion); 6] = [
+        ("raghu", quote_to_polars_df_from_series_raghu),
+        ("v0", quote_to_polars_df_from_series_v0),
+        ("v1", quote_to_polars_df_from_series_v1),
+        ("v2", quote_to_polars_df_from_series_v2),
+        ("v3", quote_to_polars_df_from_series_v3),
+        ("rows_cols", quote_to_polars_df_from_rows_cols),
+    ];
+
+    let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+    let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+    for (name, convert) in conversions {
+        let df = convert(quotes.clone()).map_err(|e| format!("{name} conversion failed: {e}"))?;
+        println!("{:#?}", &df);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run_demo() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
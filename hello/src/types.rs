@@ -0,0 +1,2968 @@
+use chrono::{Duration, NaiveDateTime};
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::cell::Cell;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<HashMap<String, QuoteData>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_type: Option<Exception>,
+}
+
+impl Quote {
+    // Instruments in `data`; an error response has none.
+    pub fn len(&self) -> usize {
+        self.data.as_ref().map_or(0, HashMap::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A payload without `status`, or with fields Kite doesn't send, is
+    // rejected instead of being read as a successful empty quote.
+    pub fn from_json_strict(json: &str) -> serde_json::Result<Quote> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct StrictQuote {
+            status: Status,
+            #[serde(default)]
+            data: Option<HashMap<String, QuoteData>>,
+            #[serde(default)]
+            message: Option<String>,
+            #[serde(default)]
+            error_type: Option<Exception>,
+        }
+
+        let strict: StrictQuote = serde_json::from_str(json)?;
+        Ok(Quote {
+            status: strict.status,
+            data: strict.data,
+            message: strict.message,
+            error_type: strict.error_type,
+        })
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quotes {
+    #[serde(flatten)]
+    pub instruments: HashMap<String, QuotesData>,
+}
+
+// 17 eight-byte numeric columns plus a 16-byte view for each of the three
+// string columns; string payloads are added on top per row.
+const ESTIMATED_FIXED_ROW_BYTES: usize = 17 * 8 + 3 * 16;
+
+impl Quotes {
+    // One value per instrument, in map order, as every column is built.
+    pub(crate) fn col<'a, T>(&'a self, f: impl Fn(&'a QuotesData) -> T) -> Vec<T> {
+        self.instruments.values().map(f).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    pub fn estimated_row_count(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn estimated_bytes(&self) -> usize {
+        self.instruments
+            .iter()
+            .map(|(symbol, q)| {
+                ESTIMATED_FIXED_ROW_BYTES
+                    + symbol.len()
+                    + q.timestamp.len()
+                    + q.last_trade_time.len()
+            })
+            .sum()
+    }
+
+    // Returns how many of `self`'s instruments were replaced by `other`'s.
+    pub fn merge(&mut self, other: Quotes, policy: MergePolicy) -> usize {
+        let mut overwritten = 0;
+        for (symbol, incoming) in other.instruments {
+            match self.instruments.entry(symbol) {
+                Entry::Vacant(entry) => {
+                    entry.insert(incoming);
+                }
+                Entry::Occupied(mut entry) => {
+                    let replace = match policy {
+                        MergePolicy::Overwrite => true,
+                        MergePolicy::KeepNewest => {
+                            incoming.parsed_timestamp() >= entry.get().parsed_timestamp()
+                        }
+                    };
+                    if replace {
+                        entry.insert(incoming);
+                        overwritten += 1;
+                    }
+                }
+            }
+        }
+        overwritten
+    }
+
+    // Keeps one symbol per `instrument_token`: the one with the newest parsed
+    // timestamp, an unparseable timestamp being older than any parseable one.
+    // Ties keep the alphabetically first symbol. Returns how many were removed.
+    pub fn dedup_by_token(&mut self) -> usize {
+        let mut keep: HashMap<u64, (Option<NaiveDateTime>, &String)> = HashMap::new();
+        for (symbol, q) in &self.instruments {
+            let candidate = (q.parsed_timestamp(), symbol);
+            keep.entry(q.instrument_token)
+                .and_modify(|best| {
+                    if candidate.0 > best.0 || (candidate.0 == best.0 && candidate.1 < best.1) {
+                        *best = candidate;
+                    }
+                })
+                .or_insert(candidate);
+        }
+        let keep: HashSet<String> = keep.into_values().map(|(_, s)| s.clone()).collect();
+        let before = self.instruments.len();
+        self.instruments.retain(|symbol, _| keep.contains(symbol));
+        before - self.instruments.len()
+    }
+
+    // Replaces NaN and infinite floats with 0.0 and returns how many were
+    // replaced. Use `non_finite_to_null` on a converted frame to get nulls.
+    pub fn sanitize_floats(&mut self) -> usize {
+        let mut replaced = 0;
+        let mut sanitize = |value: &mut f64| {
+            if !value.is_finite() {
+                *value = 0.0;
+                replaced += 1;
+            }
+        };
+        for q in self.instruments.values_mut() {
+            sanitize(&mut q.net_change);
+            q.price_fields_mut().for_each(&mut sanitize);
+        }
+        replaced
+    }
+
+    // Multiplies every price field by `factor`, e.g. `0.01` to turn paise
+    // into rupees. Affects `last_price`, `average_price`, the `ohlc` prices,
+    // both circuit limits and the depth prices; `net_change` is left as is.
+    pub fn scale_prices(&mut self, factor: f64) {
+        for q in self.instruments.values_mut() {
+            for price in q.price_fields_mut() {
+                *price *= factor;
+            }
+        }
+    }
+
+    pub fn diff(&self, previous: &Quotes) -> QuotesDiff {
+        let mut diff = QuotesDiff::default();
+        for (symbol, q) in &self.instruments {
+            match previous.instruments.get(symbol) {
+                None => diff.added.push(symbol.clone()),
+                Some(prev) if prev != q => diff.changed.push(symbol.clone()),
+                Some(_) => continue,
+            }
+            diff.last_prices.insert(
+                symbol.clone(),
+                (
+                    previous.instruments.get(symbol).map(|p| p.last_price),
+                    Some(q.last_price),
+                ),
+            );
+        }
+        for (symbol, prev) in &previous.instruments {
+            if !self.instruments.contains_key(symbol) {
+                diff.removed.push(symbol.clone());
+                diff.last_prices
+                    .insert(symbol.clone(), (Some(prev.last_price), None));
+            }
+        }
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    // bincode can't encode `#[serde(flatten)]`, so the map is stored directly.
+    #[cfg(feature = "bincode")]
+    pub fn to_bincode(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(bincode::serialize(&self.instruments)?)
+    }
+
+    #[cfg(feature = "bincode")]
+    pub fn from_bincode(bytes: &[u8]) -> Result<Quotes, Box<dyn Error>> {
+        let instruments = bincode::deserialize(bytes)?;
+        Ok(Quotes { instruments })
+    }
+
+    // Ascending by `key`, ties broken by symbol; wrap the key in
+    // `std::cmp::Reverse` for descending order.
+    pub fn iter_sorted_by<F, K>(&self, key: F) -> impl Iterator<Item = (&String, &QuotesData)>
+    where
+        F: Fn(&QuotesData) -> K,
+        K: Ord,
+    {
+        let mut entries: Vec<(&String, &QuotesData)> = self.instruments.iter().collect();
+        entries.sort_by_cached_key(|&(symbol, q)| (key(q), symbol));
+        entries.into_iter()
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&QuotesData> {
+        self.instruments.get(symbol)
+    }
+
+    pub fn depth_view(&self, symbol: &str) -> Option<&Depth> {
+        self.get(symbol).map(|q| &q.depth)
+    }
+
+    // Tries the exact key first, then an ASCII case-insensitive scan.
+    pub fn get_ignore_case(&self, symbol: &str) -> Option<&QuotesData> {
+        self.get(symbol).or_else(|| {
+            self.instruments
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(symbol))
+                .map(|(_, q)| q)
+        })
+    }
+
+    // Parsing stops at the first instrument past the limit, so an oversized
+    // payload is never fully materialised. Pass a buffered reader.
+    pub fn from_reader_limited<R: Read>(
+        r: R,
+        max_instruments: usize,
+    ) -> Result<Quotes, Box<dyn Error>> {
+        let exceeded = Cell::new(false);
+        let seed = LimitedInstruments {
+            max: max_instruments,
+            exceeded: &exceeded,
+        };
+        let mut deserializer = serde_json::Deserializer::from_reader(r);
+        let parsed = seed
+            .deserialize(&mut deserializer)
+            .and_then(|instruments| deserializer.end().map(|_| instruments));
+        match parsed {
+            Ok(instruments) => Ok(Quotes { instruments }),
+            Err(_) if exceeded.get() => Err(Box::new(TooManyInstrumentsError {
+                limit: max_instruments,
+            })),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // For a payload already in memory, e.g. an HTTP response body.
+    pub fn from_slice(bytes: &[u8]) -> Result<Quotes, Box<dyn Error>> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    // Keeps every instrument that parses and collects (symbol, error) for
+    // those that don't. A payload that stops being valid JSON part-way keeps
+    // the instruments read before that point and reports the syntax error
+    // with an empty symbol.
+    pub fn from_reader_lenient<R: Read>(r: R) -> (Quotes, Vec<(String, String)>) {
+        let mut quotes = Quotes::default();
+        let mut rejected = Vec::new();
+        let visitor = LenientInstruments {
+            instruments: &mut quotes.instruments,
+            rejected: &mut rejected,
+        };
+        let mut deserializer = serde_json::Deserializer::from_reader(r);
+        let parsed = deserializer
+            .deserialize_map(visitor)
+            .and_then(|_| deserializer.end());
+        if let Err(e) = parsed {
+            rejected.push((String::new(), e.to_string()));
+        }
+        (quotes, rejected)
+    }
+
+    // Parses like the plain `Deserialize` impl, also returning the sorted,
+    // de-duplicated names of instrument fields `QuotesData` doesn't know, as
+    // an early warning that Kite's schema has grown.
+    pub fn from_reader_logging_unknown<R: Read>(
+        r: R,
+    ) -> Result<(Quotes, Vec<String>), Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct WithExtra {
+            #[serde(flatten)]
+            data: QuotesData,
+            #[serde(flatten)]
+            extra: HashMap<String, serde_json::Value>,
+        }
+
+        let parsed: HashMap<String, WithExtra> = serde_json::from_reader(r)?;
+        let mut unknown = HashSet::new();
+        let instruments = parsed
+            .into_iter()
+            .map(|(symbol, q)| {
+                unknown.extend(q.extra.into_keys());
+                (symbol, q.data)
+            })
+            .collect();
+        let mut unknown: Vec<String> = unknown.into_iter().collect();
+        unknown.sort();
+        Ok((Quotes { instruments }, unknown))
+    }
+
+    // For wrappers that return `[{"symbol": ..., <quote fields>}, ...]`
+    // instead of a map; a repeated symbol keeps its last entry.
+    pub fn from_json_array(s: &str) -> Result<Quotes, Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct ArrayRow {
+            symbol: String,
+            #[serde(flatten)]
+            data: QuotesData,
+        }
+
+        let rows: Vec<ArrayRow> = serde_json::from_str(s)?;
+        Ok(rows.into_iter().map(|row| (row.symbol, row.data)).collect())
+    }
+
+    // Fraction of instruments in which each `QuotesData` field is set: non-zero
+    // numbers, non-empty strings, a non-default `ohlc`, a non-empty book on
+    // either side and `Some` options. Empty when there are no instruments.
+    pub fn field_coverage(&self) -> HashMap<String, f64> {
+        if self.instruments.is_empty() {
+            return HashMap::new();
+        }
+        type IsSet = fn(&QuotesData) -> bool;
+        let checks: [(&str, IsSet); 19] = [
+            ("instrument_token", |q| q.instrument_token != 0),
+            ("timestamp", |q| !q.timestamp.is_empty()),
+            ("last_trade_time", |q| !q.last_trade_time.is_empty()),
+            ("last_price", |q| q.last_price != 0.0),
+            ("last_quantity", |q| q.last_quantity != 0),
+            ("buy_quantity", |q| q.buy_quantity != 0),
+            ("sell_quantity", |q| q.sell_quantity != 0),
+            ("volume", |q| q.volume != 0),
+            ("average_price", |q| q.average_price != 0.0),
+            ("oi", |q| q.oi != 0),
+            ("oi_day_high", |q| q.oi_day_high != 0),
+            ("oi_day_low", |q| q.oi_day_low != 0),
+            ("net_change", |q| q.net_change != 0.0),
+            ("lower_circuit_limit", |q| q.lower_circuit_limit != 0.0),
+            ("upper_circuit_limit", |q| q.upper_circuit_limit != 0.0),
+            ("ohlc", |q| {
+                q.ohlc.as_ref().is_some_and(|o| *o != OhlcInner::default())
+            }),
+            ("depth", |q| {
+                !q.depth.buy.is_empty() || !q.depth.sell.is_empty()
+            }),
+            ("tradable", |q| q.tradable.is_some()),
+            ("mode", |q| q.mode.is_some()),
+        ];
+        let total = self.instruments.len() as f64;
+        checks
+            .into_iter()
+            .map(|(field, is_set)| {
+                let set = self.instruments.values().filter(|q| is_set(q)).count();
+                (field.to_owned(), set as f64 / total)
+            })
+            .collect()
+    }
+
+    // Sorted symbols whose book fails `Depth::is_well_ordered`.
+    pub fn ill_ordered_depth(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .instruments
+            .iter()
+            .filter(|(_, q)| !q.depth.is_well_ordered())
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols
+    }
+}
+
+// One line per instrument, sorted by symbol. See `Quotes::summary_table`
+// for an aligned version.
+impl fmt::Display for Quotes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (symbol, q)) in self.iter_sorted_by(|_| ()).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{symbol} last_price={} net_change={} volume={}",
+                q.last_price, q.net_change, q.volume
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Quotes {
+    // Symbol, last price, net change and volume as a table sorted by symbol;
+    // the symbol column is left-aligned and the numbers right-aligned.
+    pub fn summary_table(&self) -> String {
+        const HEADERS: [&str; 4] = ["symbol", "last_price", "net_change", "volume"];
+        let rows: Vec<[String; 4]> = self
+            .iter_sorted_by(|_| ())
+            .map(|(symbol, q)| {
+                [
+                    symbol.clone(),
+                    format!("{:.2}", q.last_price),
+                    format!("{:.2}", q.net_change),
+                    q.volume.to_string(),
+                ]
+            })
+            .collect();
+
+        let mut widths = HEADERS.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let line = |row: [&str; 4]| {
+            let [symbol, rest @ ..] = row;
+            let mut line = format!("{symbol:<width$}", width = widths[0]);
+            for (cell, width) in rest.iter().zip(&widths[1..]) {
+                line.push_str(&format!("  {cell:>width$}"));
+            }
+            line
+        };
+
+        let mut lines = vec![line(HEADERS)];
+        lines.extend(
+            rows.iter()
+                .map(|row| line(row.each_ref().map(String::as_str))),
+        );
+        lines.join("\n")
+    }
+}
+
+impl FromIterator<(String, QuotesData)> for Quotes {
+    fn from_iter<I: IntoIterator<Item = (String, QuotesData)>>(iter: I) -> Self {
+        Quotes {
+            instruments: iter.into_iter().collect(),
+        }
+    }
+}
+
+// Same (unspecified) order as `instruments.iter()`, which the conversions
+// rely on to line rows up.
+impl<'a> IntoIterator for &'a Quotes {
+    type Item = (&'a String, &'a QuotesData);
+    type IntoIter = std::collections::hash_map::Iter<'a, String, QuotesData>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.instruments.iter()
+    }
+}
+
+struct LimitedInstruments<'a> {
+    max: usize,
+    exceeded: &'a Cell<bool>,
+}
+
+impl<'de> DeserializeSeed<'de> for LimitedInstruments<'_> {
+    type Value = HashMap<String, QuotesData>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for LimitedInstruments<'_> {
+    type Value = HashMap<String, QuotesData>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map of at most {} instruments", self.max)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut instruments = HashMap::with_capacity(map.size_hint().unwrap_or(0).min(self.max));
+        while let Some(symbol) = map.next_key::<String>()? {
+            if instruments.len() == self.max && !instruments.contains_key(&symbol) {
+                self.exceeded.set(true);
+                return Err(de::Error::custom(TooManyInstrumentsError {
+                    limit: self.max,
+                }));
+            }
+            instruments.insert(symbol, map.next_value()?);
+        }
+        Ok(instruments)
+    }
+}
+
+struct LenientInstruments<'a> {
+    instruments: &'a mut HashMap<String, QuotesData>,
+    rejected: &'a mut Vec<(String, String)>,
+}
+
+impl<'de> Visitor<'de> for LenientInstruments<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of instruments")
+    }
+
+    // Each value is buffered so a bad instrument fails on its own instead of
+    // aborting the map.
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(symbol) = map.next_key::<String>()? {
+            let value: serde_json::Value = map.next_value()?;
+            match QuotesData::deserialize(value) {
+                Ok(q) => {
+                    self.instruments.insert(symbol, q);
+                }
+                Err(e) => self.rejected.push((symbol, e.to_string())),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TooManyInstrumentsError {
+    pub limit: usize,
+}
+
+impl fmt::Display for TooManyInstrumentsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "payload has more than {} instruments", self.limit)
+    }
+}
+
+impl Error for TooManyInstrumentsError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    Overwrite,
+    // Ties go to the incoming instrument; an unparseable timestamp is older
+    // than any parseable one.
+    KeepNewest,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct QuotesDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+    // (old, new) `last_price` for every symbol listed above.
+    pub(crate) last_prices: HashMap<String, (Option<f64>, Option<f64>)>,
+}
+
+impl QuotesDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// The base quote columns as parallel vectors, one entry per instrument in the
+// map's iteration order.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotesColumns {
+    pub symbols: Vec<String>,
+    pub instrument_tokens: Vec<u64>,
+    pub timestamps: Vec<String>,
+    pub last_trade_times: Vec<String>,
+    pub last_prices: Vec<f64>,
+    pub last_quantities: Vec<u64>,
+    pub buy_quantities: Vec<u64>,
+    pub sell_quantities: Vec<u64>,
+    pub volumes: Vec<u64>,
+    pub average_prices: Vec<f64>,
+    pub ois: Vec<u64>,
+    pub oi_day_highs: Vec<u64>,
+    pub oi_day_lows: Vec<u64>,
+    pub net_changes: Vec<f64>,
+    pub lower_circuit_limits: Vec<f64>,
+    pub upper_circuit_limits: Vec<f64>,
+    pub opens: Vec<Option<f64>>,
+    pub highs: Vec<Option<f64>>,
+    pub lows: Vec<Option<f64>>,
+    pub closes: Vec<Option<f64>>,
+}
+
+impl QuotesColumns {
+    pub fn from_quotes(quote: &Quotes) -> QuotesColumns {
+        QuotesColumns {
+            symbols: quote.instruments.keys().cloned().collect(),
+            instrument_tokens: quote.col(|q| q.instrument_token),
+            timestamps: quote.col(|q| q.timestamp.clone()),
+            last_trade_times: quote.col(|q| q.last_trade_time.clone()),
+            last_prices: quote.col(|q| q.last_price),
+            last_quantities: quote.col(|q| q.last_quantity),
+            buy_quantities: quote.col(|q| q.buy_quantity),
+            sell_quantities: quote.col(|q| q.sell_quantity),
+            volumes: quote.col(|q| q.volume),
+            average_prices: quote.col(|q| q.average_price),
+            ois: quote.col(|q| q.oi),
+            oi_day_highs: quote.col(|q| q.oi_day_high),
+            oi_day_lows: quote.col(|q| q.oi_day_low),
+            net_changes: quote.col(|q| q.net_change),
+            lower_circuit_limits: quote.col(|q| q.lower_circuit_limit),
+            upper_circuit_limits: quote.col(|q| q.upper_circuit_limit),
+            opens: quote.col(|q| q.ohlc.as_ref().map(|o| o.open)),
+            highs: quote.col(|q| q.ohlc.as_ref().map(|o| o.high)),
+            lows: quote.col(|q| q.ohlc.as_ref().map(|o| o.low)),
+            closes: quote.col(|q| q.ohlc.as_ref().map(|o| o.close)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+// One instrument of the standard frame as a plain struct. Field names match
+// the column names; the two `parsed_*` fields are None when the string
+// doesn't parse.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuoteRow {
+    pub symbol: String,
+    pub instrument_token: u64,
+    pub timestamp: String,
+    pub last_trade_time: String,
+    pub last_price: f64,
+    pub last_quantity: u64,
+    pub buy_quantity: u64,
+    pub sell_quantity: u64,
+    pub volume: u64,
+    pub average_price: f64,
+    pub oi: u64,
+    pub oi_day_high: u64,
+    pub oi_day_low: u64,
+    pub net_change: f64,
+    pub lower_circuit_limit: f64,
+    pub upper_circuit_limit: f64,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: Option<f64>,
+    pub parsed_timestamp: Option<NaiveDateTime>,
+    pub parsed_last_trade_time: Option<NaiveDateTime>,
+}
+
+impl Quotes {
+    // Rows follow the map's iteration order, like the DataFrame conversions.
+    pub fn to_rows(&self) -> Vec<QuoteRow> {
+        self.instruments
+            .iter()
+            .map(|(symbol, q)| QuoteRow {
+                symbol: symbol.clone(),
+                instrument_token: q.instrument_token,
+                timestamp: q.timestamp.clone(),
+                last_trade_time: q.last_trade_time.clone(),
+                last_price: q.last_price,
+                last_quantity: q.last_quantity,
+                buy_quantity: q.buy_quantity,
+                sell_quantity: q.sell_quantity,
+                volume: q.volume,
+                average_price: q.average_price,
+                oi: q.oi,
+                oi_day_high: q.oi_day_high,
+                oi_day_low: q.oi_day_low,
+                net_change: q.net_change,
+                lower_circuit_limit: q.lower_circuit_limit,
+                upper_circuit_limit: q.upper_circuit_limit,
+                open: q.ohlc.as_ref().map(|o| o.open),
+                high: q.ohlc.as_ref().map(|o| o.high),
+                low: q.ohlc.as_ref().map(|o| o.low),
+                close: q.ohlc.as_ref().map(|o| o.close),
+                parsed_timestamp: q.parsed_timestamp(),
+                parsed_last_trade_time: q.parsed_last_trade_time(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotesData {
+    #[serde(deserialize_with = "u64_from_string_or_int::deserialize")]
+    pub instrument_token: u64,
+    pub timestamp: String,
+    pub last_trade_time: String,
+    pub last_price: f64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub last_quantity: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub buy_quantity: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub sell_quantity: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub volume: u64,
+    pub average_price: f64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub oi: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub oi_day_high: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub oi_day_low: u64,
+    pub net_change: f64,
+    pub lower_circuit_limit: f64,
+    pub upper_circuit_limit: f64,
+    // Pre-open responses send `"ohlc": null` until the session opens.
+    #[serde(default)]
+    pub ohlc: Option<OhlcInner>,
+    // Absent from `quote/ltp` and `quote/ohlc` responses.
+    #[serde(default)]
+    pub depth: Depth,
+    // Only sent by some Kite versions.
+    #[serde(default)]
+    pub tradable: Option<bool>,
+    #[serde(default)]
+    pub mode: Option<String>,
+}
+
+// Quantity and volume totals clamp at `u64::MAX` instead of wrapping (or
+// panicking in debug builds) on adversarial input.
+pub(crate) fn saturating_sum(values: impl IntoIterator<Item = u64>) -> u64 {
+    values.into_iter().fold(0, u64::saturating_add)
+}
+
+fn parse_quote_time(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    optional_naive_date_time_from_str::parse(s).ok()
+}
+
+impl QuotesData {
+    // Pre-open instruments come with empty timestamps; those, like any
+    // unparseable string, are None rather than an error or the epoch.
+    pub fn parsed_timestamp(&self) -> Option<NaiveDateTime> {
+        parse_quote_time(&self.timestamp)
+    }
+
+    pub fn parsed_last_trade_time(&self) -> Option<NaiveDateTime> {
+        parse_quote_time(&self.last_trade_time)
+    }
+
+    // Compact JSON in the field order of Kite's `/quote` response, with
+    // whole-valued prices written the way Kite writes them (`1396`, not
+    // `1396.0`), so the output can be byte-compared with a captured
+    // response. `tradable` and `mode` follow `depth` when present; non-finite
+    // prices become `null`.
+    pub fn to_canonical_json(&self) -> String {
+        fn number(out: &mut String, v: f64) {
+            if v.is_finite() {
+                out.push_str(&v.to_string());
+            } else {
+                out.push_str("null");
+            }
+        }
+        fn levels(out: &mut String, levels: &[OrderDepth]) {
+            out.push('[');
+            for (i, d) in levels.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str("{\"price\":");
+                number(out, d.price);
+                out.push_str(&format!(
+                    ",\"quantity\":{},\"orders\":{}}}",
+                    d.quantity, d.orders
+                ));
+            }
+            out.push(']');
+        }
+        let string = |v: &str| serde_json::to_string(v).expect("strings serialize to JSON");
+
+        let mut out = format!(
+            "{{\"instrument_token\":{},\"timestamp\":{},\"last_trade_time\":{},\"last_price\":",
+            self.instrument_token,
+            string(&self.timestamp),
+            string(&self.last_trade_time)
+        );
+        number(&mut out, self.last_price);
+        out.push_str(&format!(
+            ",\"last_quantity\":{},\"buy_quantity\":{},\"sell_quantity\":{},\"volume\":{},\"average_price\":",
+            self.last_quantity, self.buy_quantity, self.sell_quantity, self.volume
+        ));
+        number(&mut out, self.average_price);
+        out.push_str(&format!(
+            ",\"oi\":{},\"oi_day_high\":{},\"oi_day_low\":{}",
+            self.oi, self.oi_day_high, self.oi_day_low
+        ));
+        for (name, v) in [
+            ("net_change", self.net_change),
+            ("lower_circuit_limit", self.lower_circuit_limit),
+            ("upper_circuit_limit", self.upper_circuit_limit),
+        ] {
+            out.push_str(&format!(",\"{name}\":"));
+            number(&mut out, v);
+        }
+        match &self.ohlc {
+            Some(ohlc) => {
+                out.push_str(",\"ohlc\":{");
+                for (i, (name, v)) in [
+                    ("open", ohlc.open),
+                    ("high", ohlc.high),
+                    ("low", ohlc.low),
+                    ("close", ohlc.close),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&format!("\"{name}\":"));
+                    number(&mut out, v);
+                }
+                out.push('}');
+            }
+            None => out.push_str(",\"ohlc\":null"),
+        }
+        out.push_str(",\"depth\":{\"buy\":");
+        levels(&mut out, &self.depth.buy);
+        out.push_str(",\"sell\":");
+        levels(&mut out, &self.depth.sell);
+        out.push('}');
+        if let Some(tradable) = self.tradable {
+            out.push_str(&format!(",\"tradable\":{tradable}"));
+        }
+        if let Some(mode) = &self.mode {
+            out.push_str(&format!(",\"mode\":{}", string(mode)));
+        }
+        out.push('}');
+        out
+    }
+
+    pub fn total_buy_depth(&self) -> u64 {
+        saturating_sum(self.depth.buy.iter().map(|d| d.quantity))
+    }
+
+    pub fn total_sell_depth(&self) -> u64 {
+        saturating_sum(self.depth.sell.iter().map(|d| d.quantity))
+    }
+
+    // Top-of-book (bid, ask); None when either side is empty or zero-priced.
+    pub fn best_bid_ask(&self) -> Option<(f64, f64)> {
+        let bid = self
+            .depth
+            .buy
+            .first()
+            .map(|d| d.price)
+            .filter(|&p| p != 0.0)?;
+        let ask = self
+            .depth
+            .sell
+            .first()
+            .map(|d| d.price)
+            .filter(|&p| p != 0.0)?;
+        Some((bid, ask))
+    }
+
+    // The side with more displayed quantity; None on a tie, which includes
+    // an empty book.
+    pub fn dominant_side(&self) -> Option<Side> {
+        match self.total_buy_depth().cmp(&self.total_sell_depth()) {
+            std::cmp::Ordering::Greater => Some(Side::Buy),
+            std::cmp::Ordering::Less => Some(Side::Sell),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn best_spread(&self) -> Option<f64> {
+        self.best_bid_ask().map(|(bid, ask)| ask - bid)
+    }
+
+    // Average fill price for a market order of `quantity`: a buy walks the
+    // sell levels and a sell walks the buy levels. None for a zero quantity or
+    // when the book can't fill it; zero-priced padding levels are skipped.
+    pub fn sweep_cost(&self, side: Side, quantity: u64) -> Option<f64> {
+        let levels = match side {
+            Side::Buy => &self.depth.sell,
+            Side::Sell => &self.depth.buy,
+        };
+        let mut remaining = quantity;
+        let mut notional = 0.0;
+        for level in levels.iter().filter(|d| d.price != 0.0) {
+            if remaining == 0 {
+                break;
+            }
+            let fill = remaining.min(level.quantity);
+            notional += fill as f64 * level.price;
+            remaining -= fill;
+        }
+        (quantity != 0 && remaining == 0).then(|| notional / quantity as f64)
+    }
+
+    // Overwrites only the fields the patch carries; `ohlc` and `depth` are
+    // replaced whole.
+    pub fn apply_update(&mut self, update: &QuotesDataPatch) {
+        if let Some(v) = update.instrument_token {
+            self.instrument_token = v;
+        }
+        if let Some(v) = &update.timestamp {
+            self.timestamp = v.clone();
+        }
+        if let Some(v) = &update.last_trade_time {
+            self.last_trade_time = v.clone();
+        }
+        if let Some(v) = update.last_price {
+            self.last_price = v;
+        }
+        if let Some(v) = update.last_quantity {
+            self.last_quantity = v;
+        }
+        if let Some(v) = update.buy_quantity {
+            self.buy_quantity = v;
+        }
+        if let Some(v) = update.sell_quantity {
+            self.sell_quantity = v;
+        }
+        if let Some(v) = update.volume {
+            self.volume = v;
+        }
+        if let Some(v) = update.average_price {
+            self.average_price = v;
+        }
+        if let Some(v) = update.oi {
+            self.oi = v;
+        }
+        if let Some(v) = update.oi_day_high {
+            self.oi_day_high = v;
+        }
+        if let Some(v) = update.oi_day_low {
+            self.oi_day_low = v;
+        }
+        if let Some(v) = update.net_change {
+            self.net_change = v;
+        }
+        if let Some(v) = update.lower_circuit_limit {
+            self.lower_circuit_limit = v;
+        }
+        if let Some(v) = update.upper_circuit_limit {
+            self.upper_circuit_limit = v;
+        }
+        if let Some(v) = &update.ohlc {
+            self.ohlc = Some(v.clone());
+        }
+        if let Some(v) = &update.depth {
+            self.depth = v.clone();
+        }
+        if let Some(v) = update.tradable {
+            self.tradable = Some(v);
+        }
+        if let Some(v) = &update.mode {
+            self.mode = Some(v.clone());
+        }
+    }
+
+    fn price_fields_mut(&mut self) -> impl Iterator<Item = &mut f64> {
+        [
+            &mut self.last_price,
+            &mut self.average_price,
+            &mut self.lower_circuit_limit,
+            &mut self.upper_circuit_limit,
+        ]
+        .into_iter()
+        .chain(self.ohlc.iter_mut().flat_map(OhlcInner::prices_mut))
+        .chain(
+            self.depth
+                .buy
+                .iter_mut()
+                .chain(self.depth.sell.iter_mut())
+                .map(|level| &mut level.price),
+        )
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotesOpt {
+    #[serde(flatten)]
+    pub instruments: HashMap<String, QuotesDataOpt>,
+}
+
+// A sparse update for `QuotesData::apply_update`; absent fields stay `None`.
+pub type QuotesDataPatch = QuotesDataOpt;
+
+// Mirrors `QuotesData` but keeps absent fields as `None` so they become
+// nulls in the frame rather than indistinguishable zeros.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotesDataOpt {
+    pub instrument_token: Option<u64>,
+    pub timestamp: Option<String>,
+    pub last_trade_time: Option<String>,
+    pub last_price: Option<f64>,
+    pub last_quantity: Option<u64>,
+    pub buy_quantity: Option<u64>,
+    pub sell_quantity: Option<u64>,
+    pub volume: Option<u64>,
+    pub average_price: Option<f64>,
+    pub oi: Option<u64>,
+    pub oi_day_high: Option<u64>,
+    pub oi_day_low: Option<u64>,
+    pub net_change: Option<f64>,
+    pub lower_circuit_limit: Option<f64>,
+    pub upper_circuit_limit: Option<f64>,
+    pub ohlc: Option<OhlcInner>,
+    pub depth: Option<Depth>,
+    pub tradable: Option<bool>,
+    pub mode: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuoteData {
+    #[serde(deserialize_with = "u64_from_string_or_int::deserialize")]
+    pub instrument_token: u64,
+    #[serde(
+        with = "optional_naive_date_time_from_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timestamp: Option<NaiveDateTime>,
+    #[serde(
+        with = "optional_naive_date_time_from_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_trade_time: Option<NaiveDateTime>,
+    pub last_price: f64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub last_quantity: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub buy_quantity: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub sell_quantity: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub volume: u64,
+    pub average_price: f64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub oi: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub oi_day_high: u64,
+    #[serde(deserialize_with = "u64_from_number::deserialize")]
+    pub oi_day_low: u64,
+    pub net_change: f64,
+    pub lower_circuit_limit: f64,
+    pub upper_circuit_limit: f64,
+    pub ohlc: OhlcInner,
+    pub depth: Depth,
+}
+
+pub fn round_to_tick(price: f64, tick: f64) -> f64 {
+    if tick <= 0.0 || !price.is_finite() {
+        return price;
+    }
+    let rounded = (price / tick).round() * tick;
+    // Multiplying back by a tick like 0.05 leaves representation noise
+    // (1412.9500000000003), so snap to the tick's own decimal places.
+    let scale = 10f64.powi(tick_decimals(tick));
+    (rounded * scale).round() / scale
+}
+
+fn tick_decimals(tick: f64) -> i32 {
+    let mut decimals = 0;
+    while decimals < 9 {
+        let scaled = tick * 10f64.powi(decimals);
+        if (scaled - scaled.round()).abs() < 1e-9 {
+            break;
+        }
+        decimals += 1;
+    }
+    decimals
+}
+
+pub fn normalize_prices(quote: &mut Quotes, tick: f64) {
+    for q in quote.instruments.values_mut() {
+        q.last_price = round_to_tick(q.last_price, tick);
+        for price in q.ohlc.iter_mut().flat_map(OhlcInner::prices_mut) {
+            *price = round_to_tick(*price, tick);
+        }
+        for level in q.depth.buy.iter_mut().chain(q.depth.sell.iter_mut()) {
+            level.price = round_to_tick(level.price, tick);
+        }
+    }
+}
+
+// Sorted symbols last updated more than `max_age` before `now`; a missing or
+// unparseable timestamp counts as stale.
+pub fn stale_instruments(quote: &Quotes, now: NaiveDateTime, max_age: Duration) -> Vec<String> {
+    let mut symbols: Vec<String> = quote
+        .instruments
+        .iter()
+        .filter(|(_, q)| q.parsed_timestamp().is_none_or(|ts| now - ts > max_age))
+        .map(|(symbol, _)| symbol.clone())
+        .collect();
+    symbols.sort();
+    symbols
+}
+
+// Instruments whose best bid is at or above the best ask, sorted by symbol.
+// One-sided books are skipped, as `QuotesData::best_bid_ask` returns None.
+pub fn crossed_or_locked(quote: &Quotes) -> Vec<(String, BookState)> {
+    let mut books: Vec<(String, BookState)> = quote
+        .instruments
+        .iter()
+        .filter_map(|(symbol, q)| {
+            let (bid, ask) = q.best_bid_ask()?;
+            let state = if bid > ask {
+                BookState::Crossed
+            } else if bid == ask {
+                BookState::Locked
+            } else {
+                return None;
+            };
+            Some((symbol.clone(), state))
+        })
+        .collect();
+    books.sort_by(|a, b| a.0.cmp(&b.0));
+    books
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookState {
+    Crossed,
+    Locked,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Depth {
+    pub buy: Vec<OrderDepth>,
+    pub sell: Vec<OrderDepth>,
+}
+
+impl Depth {
+    // Buy prices must be non-increasing and sell prices non-decreasing.
+    // Zero-priced levels are padding and may only trail the real ones.
+    pub fn is_well_ordered(&self) -> bool {
+        fn side_ok(levels: &[OrderDepth], in_order: fn(f64, f64) -> bool) -> bool {
+            let filled = levels.iter().take_while(|d| d.price != 0.0).count();
+            levels[filled..].iter().all(|d| d.price == 0.0)
+                && levels[..filled]
+                    .windows(2)
+                    .all(|w| in_order(w[0].price, w[1].price))
+        }
+        side_ok(&self.buy, |a, b| a >= b) && side_ok(&self.sell, |a, b| a <= b)
+    }
+
+    // Bids on the left and asks on the right, one level per line, each
+    // column right-aligned to its widest cell.
+    pub fn to_pretty_string(&self) -> String {
+        const HEADERS: [&str; 6] = ["orders", "qty", "bid", "ask", "qty", "orders"];
+        let cells = |level: Option<&OrderDepth>| match level {
+            Some(d) => [
+                d.orders.to_string(),
+                d.quantity.to_string(),
+                format!("{:.2}", d.price),
+            ],
+            None => Default::default(),
+        };
+        let rows: Vec<[String; 6]> = (0..self.buy.len().max(self.sell.len()))
+            .map(|i| {
+                let [buy_orders, buy_qty, bid] = cells(self.buy.get(i));
+                let [sell_orders, sell_qty, ask] = cells(self.sell.get(i));
+                [buy_orders, buy_qty, bid, ask, sell_qty, sell_orders]
+            })
+            .collect();
+
+        let mut widths = HEADERS.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let line = |row: [&str; 6]| {
+            let padded: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:>width$}"))
+                .collect();
+            format!("{} | {}", padded[..3].join(" "), padded[3..].join(" "))
+        };
+
+        let mut lines = vec![line(HEADERS)];
+        lines.extend(
+            rows.iter()
+                .map(|row| line(row.each_ref().map(String::as_str))),
+        );
+        lines.join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderDepth {
+    pub price: f64,
+    pub quantity: u64,
+    pub orders: u64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhlcInner {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl OhlcInner {
+    fn prices_mut(&mut self) -> [&mut f64; 4] {
+        [
+            &mut self.open,
+            &mut self.high,
+            &mut self.low,
+            &mut self.close,
+        ]
+    }
+}
+
+// A streaming ticker update. LTP-mode ticks carry only the token and price,
+// so the remaining fields default when absent; a missing `ohlc` stays None
+// rather than reading as zero prices.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    pub instrument_token: u64,
+    pub last_price: f64,
+    #[serde(default)]
+    pub volume: u64,
+    #[serde(default)]
+    pub ohlc: Option<OhlcInner>,
+    #[serde(default)]
+    pub depth: Depth,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Historical {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<HistoricalData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_type: Option<Exception>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoricalData {
+    pub candles: Vec<Candle>,
+}
+
+// A single historical candle. Kite sends these as positional arrays
+// `[timestamp, open, high, low, close, volume]`, with a trailing `oi`
+// element when open interest was requested.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Candle {
+    pub timestamp: NaiveDateTime,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: u64,
+    pub oi: Option<u64>,
+}
+
+impl Serialize for Candle {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let len = if self.oi.is_some() { 7 } else { 6 };
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        seq.serialize_element(
+            &self
+                .timestamp
+                .format(candle_timestamp::NAIVE_DT_FORMAT)
+                .to_string(),
+        )?;
+        seq.serialize_element(&self.open)?;
+        seq.serialize_element(&self.high)?;
+        seq.serialize_element(&self.low)?;
+        seq.serialize_element(&self.close)?;
+        seq.serialize_element(&self.volume)?;
+        if let Some(oi) = self.oi {
+            seq.serialize_element(&oi)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Candle {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CandleVisitor;
+
+        impl<'de> Visitor<'de> for CandleVisitor {
+            type Value = Candle;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array [timestamp, open, high, low, close, volume, oi?]")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Candle, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let timestamp: String = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let timestamp = candle_timestamp::parse(&timestamp).map_err(de::Error::custom)?;
+                let open = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let high = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                let low = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+                let close = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(4, &self))?;
+                let volume = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(5, &self))?;
+                let oi = seq.next_element()?;
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(8, &self));
+                }
+                Ok(Candle {
+                    timestamp,
+                    open,
+                    high,
+                    low,
+                    close,
+                    volume,
+                    oi,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(CandleVisitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Exception {
+    TokenException,
+    UserException,
+    OrderException,
+    InputException,
+    NetworkException,
+    DataException,
+    GeneralException,
+}
+
+impl Exception {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Exception::TokenException => "TokenException",
+            Exception::UserException => "UserException",
+            Exception::OrderException => "OrderException",
+            Exception::InputException => "InputException",
+            Exception::NetworkException => "NetworkException",
+            Exception::DataException => "DataException",
+            Exception::GeneralException => "GeneralException",
+        }
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error for Exception {}
+
+impl FromStr for Exception {
+    type Err = ParseExceptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TokenException" => Ok(Exception::TokenException),
+            "UserException" => Ok(Exception::UserException),
+            "OrderException" => Ok(Exception::OrderException),
+            "InputException" => Ok(Exception::InputException),
+            "NetworkException" => Ok(Exception::NetworkException),
+            "DataException" => Ok(Exception::DataException),
+            "GeneralException" => Ok(Exception::GeneralException),
+            _ => Err(ParseExceptionError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseExceptionError(pub String);
+
+impl fmt::Display for ParseExceptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown Kite exception type: {:?}", self.0)
+    }
+}
+
+impl Error for ParseExceptionError {}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    #[default]
+    Success,
+    Error,
+    Failed,
+}
+
+// Kite sends lowercase, but some proxies capitalise the status, so any ASCII
+// casing is accepted. Serialization stays lowercase.
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const VARIANTS: &[&str] = &["success", "error", "failed"];
+        let value = String::deserialize(deserializer)?;
+        match value.to_ascii_lowercase().as_str() {
+            "success" => Ok(Status::Success),
+            "error" => Ok(Status::Error),
+            "failed" => Ok(Status::Failed),
+            _ => Err(de::Error::unknown_variant(&value, VARIANTS)),
+        }
+    }
+}
+
+// An `EXCHANGE:TRADINGSYMBOL` key, validated on construction.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct InstrumentKey(String);
+
+impl InstrumentKey {
+    pub fn parse(s: &str) -> Result<InstrumentKey, ParseInstrumentKeyError> {
+        match s.split_once(':') {
+            Some((exchange, tradingsymbol))
+                if !exchange.is_empty()
+                    && exchange
+                        .bytes()
+                        .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+                    && !tradingsymbol.is_empty()
+                    && !tradingsymbol.contains(char::is_whitespace) =>
+            {
+                Ok(InstrumentKey(s.to_owned()))
+            }
+            _ => Err(ParseInstrumentKeyError(s.to_owned())),
+        }
+    }
+
+    pub fn exchange(&self) -> &str {
+        self.0.split_once(':').map_or("", |(exchange, _)| exchange)
+    }
+
+    pub fn tradingsymbol(&self) -> &str {
+        self.0
+            .split_once(':')
+            .map_or("", |(_, tradingsymbol)| tradingsymbol)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for InstrumentKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for InstrumentKey {
+    type Err = ParseInstrumentKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InstrumentKey::parse(s)
+    }
+}
+
+impl TryFrom<String> for InstrumentKey {
+    type Error = ParseInstrumentKeyError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        InstrumentKey::parse(&s)
+    }
+}
+
+impl From<InstrumentKey> for String {
+    fn from(key: InstrumentKey) -> String {
+        key.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseInstrumentKeyError(pub String);
+
+impl fmt::Display for ParseInstrumentKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected an EXCHANGE:TRADINGSYMBOL key, got {:?}",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseInstrumentKeyError {}
+
+// `Quotes` keyed by validated instrument keys; deserializing fails on the
+// first malformed key.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotesTyped {
+    #[serde(flatten)]
+    pub instruments: HashMap<InstrumentKey, QuotesData>,
+}
+
+impl TryFrom<Quotes> for QuotesTyped {
+    type Error = ParseInstrumentKeyError;
+
+    fn try_from(quotes: Quotes) -> Result<Self, Self::Error> {
+        let instruments = quotes
+            .instruments
+            .into_iter()
+            .map(|(symbol, q)| Ok((InstrumentKey::try_from(symbol)?, q)))
+            .collect::<Result<_, _>>()?;
+        Ok(QuotesTyped { instruments })
+    }
+}
+
+impl From<QuotesTyped> for Quotes {
+    fn from(quotes: QuotesTyped) -> Self {
+        let instruments = quotes
+            .instruments
+            .into_iter()
+            .map(|(key, q)| (key.into(), q))
+            .collect();
+        Quotes { instruments }
+    }
+}
+
+pub mod u64_from_string_or_int {
+    use serde::{de, Deserializer};
+    use std::fmt;
+
+    struct U64Visitor;
+
+    impl<'de> de::Visitor<'de> for U64Visitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an unsigned integer or a string containing one")
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<u64, E> {
+            Ok(v)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<u64, E> {
+            u64::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<u64, E> {
+            v.trim()
+                .parse()
+                .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Binary formats aren't self-describing and always carry the integer.
+        if !deserializer.is_human_readable() {
+            return deserializer.deserialize_u64(U64Visitor);
+        }
+        deserializer.deserialize_any(U64Visitor)
+    }
+}
+
+// Quantities from feeds that send whole numbers as floats (`5191.0`). A
+// fractional, negative or out-of-range float is an error, never rounded.
+pub mod u64_from_number {
+    use serde::{de, Deserializer};
+    use std::fmt;
+
+    struct U64Visitor;
+
+    impl<'de> de::Visitor<'de> for U64Visitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an unsigned integer or a whole-valued float")
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<u64, E> {
+            Ok(v)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<u64, E> {
+            u64::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<u64, E> {
+            // 2^64 is the first float past `u64::MAX`.
+            if v.fract() == 0.0 && (0.0..18_446_744_073_709_551_616.0).contains(&v) {
+                Ok(v as u64)
+            } else {
+                Err(E::invalid_value(de::Unexpected::Float(v), &self))
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return deserializer.deserialize_u64(U64Visitor);
+        }
+        deserializer.deserialize_any(U64Visitor)
+    }
+}
+
+pub mod optional_naive_date_from_str {
+    use chrono::NaiveDate;
+    use serde::{de, ser, Deserialize, Deserializer};
+    const DT_FORMAT: &str = "%Y-%m-%d";
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let maybe_naive_date_string: Option<String> = match Deserialize::deserialize(deserializer) {
+            Ok(naive_date_string) => Some(naive_date_string),
+            Err(_) => None,
+        };
+
+        match maybe_naive_date_string {
+            Some(naive_date_string) => NaiveDate::parse_from_str(&naive_date_string, DT_FORMAT)
+                .map(Some)
+                .map_err(de::Error::custom),
+            None => Ok(None),
+        }
+    }
+    pub fn serialize<S>(naive_date: &Option<NaiveDate>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match *naive_date {
+            Some(ref dt) => serializer
+                .serialize_some(&dt.format(DT_FORMAT).to_string())
+                .map_err(ser::Error::custom),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+pub mod optional_naive_date_time_from_str {
+    use chrono::{NaiveDateTime, Timelike};
+    use serde::{de, ser, Deserialize, Deserializer};
+    const DT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+    const DT_FORMAT_FRACTIONAL: &str = "%Y-%m-%d %H:%M:%S%.f";
+    pub const DT_FORMATS: &[&str] = &[DT_FORMAT, DT_FORMAT_FRACTIONAL];
+
+    pub fn parse(naive_date_time_string: &str) -> Result<NaiveDateTime, chrono::ParseError> {
+        DT_FORMATS[1..].iter().fold(
+            NaiveDateTime::parse_from_str(naive_date_time_string, DT_FORMATS[0]),
+            |result, format| {
+                result.or_else(|_| NaiveDateTime::parse_from_str(naive_date_time_string, format))
+            },
+        )
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<NaiveDateTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Only an explicit null or an empty string means "no timestamp"; any
+        // other non-string value is a type error and is surfaced as such.
+        let maybe_naive_date_time_string: Option<String> = Deserialize::deserialize(deserializer)?;
+
+        match maybe_naive_date_time_string.as_deref() {
+            None | Some("") => Ok(None),
+            Some(naive_date_time_string) => parse(naive_date_time_string)
+                .map(Some)
+                .map_err(de::Error::custom),
+        }
+    }
+    pub fn serialize<S>(
+        naive_date_time: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        // Whole seconds keep the original format; sub-second values keep
+        // their fraction so they round-trip.
+        match *naive_date_time {
+            Some(ref dt) => {
+                let format = if dt.nanosecond() == 0 {
+                    DT_FORMAT
+                } else {
+                    DT_FORMAT_FRACTIONAL
+                };
+                serializer
+                    .serialize_some(&dt.format(format).to_string())
+                    .map_err(ser::Error::custom)
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+pub mod candle_timestamp {
+    use chrono::{DateTime, NaiveDateTime, ParseError};
+    pub const DT_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z";
+    pub const NAIVE_DT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+    // Kite stamps candles in exchange time (+0530); the offset is dropped so
+    // candles line up with the naive timestamps used by the quote payloads.
+    pub fn parse(s: &str) -> Result<NaiveDateTime, ParseError> {
+        match DateTime::parse_from_str(s, DT_FORMAT) {
+            Ok(dt) => Ok(dt.naive_local()),
+            Err(_) => NaiveDateTime::parse_from_str(s, NAIVE_DT_FORMAT),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_json_from_file;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_quote_json() -> serde_json::Result<()> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quote.json").unwrap();
+        let deserialized: Quote = serde_json::from_reader(jsonfile)?;
+        println!("{:#?}", &deserialized);
+        let mut data: HashMap<String, QuoteData> = HashMap::new();
+        data.insert(
+            "NSE:INFY".to_owned(),
+            QuoteData {
+                instrument_token: 408065,
+                timestamp: Some(NaiveDate::from_ymd(2021, 6, 8).and_hms(15, 45, 56)),
+                last_trade_time: Some(NaiveDate::from_ymd(2021, 6, 8).and_hms(15, 45, 52)),
+                last_price: 1412.95,
+                last_quantity: 5,
+                buy_quantity: 0,
+                sell_quantity: 5191,
+                volume: 7360198,
+                average_price: 1412.47,
+                oi: 0,
+                oi_day_high: 0,
+                oi_day_low: 0,
+                net_change: 0.0,
+                lower_circuit_limit: 1250.7,
+                upper_circuit_limit: 1528.6,
+                ohlc: OhlcInner {
+                    open: 1396.0,
+                    high: 1421.75,
+                    low: 1395.55,
+                    close: 1389.65,
+                },
+                depth: Depth {
+                    buy: [
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                    ]
+                    .to_vec(),
+                    sell: [
+                        OrderDepth {
+                            price: 1412.95,
+                            quantity: 5191,
+                            orders: 13,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                        OrderDepth {
+                            price: 0.0,
+                            quantity: 0,
+                            orders: 0,
+                        },
+                    ]
+                    .to_vec(),
+                },
+            },
+        );
+        assert_eq!(
+            deserialized,
+            Quote {
+                status: Status::Success,
+                data: Some(data),
+                ..Quote::default()
+            }
+        );
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        println!("{:#?}", &serialized);
+        // assert_eq!(raw_data, serialized);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_no_instruments() -> serde_json::Result<()> {
+        let raw_data = r#"{"status":"success","data":{}}"#;
+        let deserialized: Quote = serde_json::from_str(raw_data)?;
+        println!("{:#?}", &deserialized);
+        assert_eq!(
+            deserialized,
+            Quote {
+                status: Status::Success,
+                data: Some(HashMap::new()),
+                ..Quote::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_error() -> serde_json::Result<()> {
+        let raw_data =
+            r#"{"status":"error","message":"Error message","error_type":"GeneralException"}"#;
+        let deserialized: Quote = serde_json::from_str(raw_data)?;
+        println!("{:#?}", &deserialized);
+        assert_eq!(
+            deserialized,
+            Quote {
+                status: Status::Error,
+                data: None,
+                message: Some("Error message".to_owned()),
+                error_type: Some(Exception::GeneralException),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_candle_with_oi() -> serde_json::Result<()> {
+        let raw_data =
+            r#"["2019-12-04T09:15:00+0530",12009.9,12019.35,12001.25,12001.5,163275,13667775]"#;
+        let deserialized: Candle = serde_json::from_str(raw_data)?;
+        assert_eq!(deserialized.oi, Some(13667775));
+        let serialized = serde_json::to_string(&deserialized)?;
+        let reparsed: Candle = serde_json::from_str(&serialized)?;
+        assert_eq!(deserialized, reparsed);
+        assert!(serde_json::from_str::<Candle>(r#"["2019-12-04T09:15:00+0530",1.0]"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_strict() -> serde_json::Result<()> {
+        let raw_data = r#"{"status":"success","data":{}}"#;
+        assert_eq!(
+            Quote::from_json_strict(raw_data)?,
+            serde_json::from_str::<Quote>(raw_data)?
+        );
+
+        let missing_status = r#"{"data":{}}"#;
+        assert!(serde_json::from_str::<Quote>(missing_status).is_err());
+        assert!(Quote::from_json_strict(missing_status).is_err());
+
+        let unknown_field = r#"{"status":"success","data":{},"extra":1}"#;
+        assert!(serde_json::from_str::<Quote>(unknown_field).is_ok());
+        assert!(Quote::from_json_strict(unknown_field).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_any_case() -> serde_json::Result<()> {
+        for raw in [r#""success""#, r#""Success""#, r#""SUCCESS""#] {
+            assert_eq!(serde_json::from_str::<Status>(raw)?, Status::Success);
+        }
+        assert_eq!(serde_json::from_str::<Status>(r#""Error""#)?, Status::Error);
+        assert_eq!(
+            serde_json::from_str::<Status>(r#""failed""#)?,
+            Status::Failed
+        );
+        assert!(serde_json::from_str::<Status>(r#""ok""#).is_err());
+        assert_eq!(serde_json::to_string(&Status::Success)?, r#""success""#);
+
+        let quote: Quote = serde_json::from_str(r#"{"status":"Success","data":{}}"#)?;
+        assert_eq!(quote.status, Status::Success);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exception_display_and_parse() {
+        let exceptions = [
+            Exception::TokenException,
+            Exception::UserException,
+            Exception::OrderException,
+            Exception::InputException,
+            Exception::NetworkException,
+            Exception::DataException,
+            Exception::GeneralException,
+        ];
+        for exception in exceptions {
+            let name = exception.to_string();
+            assert_eq!(
+                serde_json::to_string(&exception).unwrap(),
+                format!("\"{}\"", name)
+            );
+            assert_eq!(name.parse::<Exception>(), Ok(exception));
+        }
+        assert!("NotAnException".parse::<Exception>().is_err());
+
+        let boxed: Box<dyn Error> = Box::new(Exception::TokenException);
+        assert_eq!(boxed.to_string(), "TokenException");
+    }
+
+    #[test]
+    fn test_quotes_estimates() -> Result<(), Box<dyn Error>> {
+        assert_eq!(Quotes::default().estimated_row_count(), 0);
+        assert_eq!(Quotes::default().estimated_bytes(), 0);
+
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        assert_eq!(quotes.estimated_row_count(), 181);
+        // Every mock key is a 7-digit token and both timestamps are 19 chars.
+        assert_eq!(
+            quotes.estimated_bytes(),
+            181 * (ESTIMATED_FIXED_ROW_BYTES + 7 + 19 + 19)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_len() -> Result<(), Box<dyn Error>> {
+        assert_eq!(Quotes::default().len(), 0);
+        assert!(Quotes::default().is_empty());
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        assert_eq!(quotes.len(), quotes.instruments.len());
+        assert!(!quotes.is_empty());
+
+        let error: Quote = serde_json::from_str(r#"{"status":"error","message":"m"}"#)?;
+        assert_eq!(error.len(), 0);
+        assert!(error.is_empty());
+        let empty: Quote = serde_json::from_str(r#"{"status":"success","data":{}}"#)?;
+        assert!(empty.is_empty());
+        let quote: Quote =
+            serde_json::from_reader(read_json_from_file("kiteconnect-mocks/quote.json")?)?;
+        assert_eq!(quote.len(), quote.data.as_ref().unwrap().len());
+        assert!(!quote.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantities_from_number() -> Result<(), Box<dyn Error>> {
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quote.json")?;
+        let expected: Quote = serde_json::from_str(&raw)?;
+        let data = &expected.data.as_ref().unwrap()["NSE:INFY"];
+        let mut floaty = raw.clone();
+        for (name, quantity) in [
+            ("buy_quantity", data.buy_quantity),
+            ("last_quantity", data.last_quantity),
+        ] {
+            let field = format!(r#""{name}": {quantity}"#);
+            assert!(floaty.contains(&field));
+            floaty = floaty.replace(&field, &format!(r#""{name}": {quantity}.0"#));
+        }
+        assert_eq!(serde_json::from_str::<Quote>(&floaty)?, expected);
+
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quotes.json")?;
+        let expected: Quotes = serde_json::from_str(&raw)?;
+        let mut value: serde_json::Value = serde_json::from_str(&raw)?;
+        let q = &mut value["8960002"];
+        for field in ["volume", "sell_quantity", "oi"] {
+            q[field] = (q[field].as_u64().unwrap() as f64).into();
+        }
+        assert!(q["volume"].is_f64());
+        assert_eq!(serde_json::from_value::<Quotes>(value.clone())?, expected);
+
+        value["8960002"]["oi"] = 12.5.into();
+        assert!(serde_json::from_value::<Quotes>(value.clone()).is_err());
+        value["8960002"]["oi"] = (-1.0).into();
+        assert!(serde_json::from_value::<Quotes>(value).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_instrument_token_as_string() -> Result<(), Box<dyn Error>> {
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quote.json")?;
+        let expected: Quote = serde_json::from_str(&raw)?;
+        let stringly = raw.replace(
+            r#""instrument_token": 408065"#,
+            r#""instrument_token": "408065""#,
+        );
+        assert_ne!(raw, stringly);
+        assert_eq!(serde_json::from_str::<Quote>(&stringly)?, expected);
+
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quotes.json")?;
+        let expected: Quotes = serde_json::from_str(&raw)?;
+        let stringly = raw.replace(
+            r#""instrument_token": 8960002"#,
+            r#""instrument_token": "8960002""#,
+        );
+        let quotes: Quotes = serde_json::from_str(&stringly)?;
+        assert_eq!(quotes, expected);
+        // Tokens still serialize as plain numbers.
+        assert!(serde_json::to_string(&quotes.instruments["8960002"])?
+            .starts_with(r#"{"instrument_token":8960002,"#));
+
+        for bad in [r#""INFY""#, "-1"] {
+            let json = raw.replace("8960002,", &format!("{bad},"));
+            assert!(serde_json::from_str::<Quotes>(&json).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_ordering() -> Result<(), Box<dyn Error>> {
+        let level = |price| OrderDepth {
+            price,
+            ..OrderDepth::default()
+        };
+        let depth = |buy: &[f64], sell: &[f64]| Depth {
+            buy: buy.iter().copied().map(level).collect(),
+            sell: sell.iter().copied().map(level).collect(),
+        };
+        assert!(Depth::default().is_well_ordered());
+        assert!(depth(&[10.0, 9.5, 9.5, 0.0, 0.0], &[10.5, 11.0, 0.0]).is_well_ordered());
+        assert!(depth(&[0.0, 0.0], &[0.0]).is_well_ordered());
+        assert!(!depth(&[9.5, 10.0], &[10.5]).is_well_ordered());
+        assert!(!depth(&[10.0], &[11.0, 10.5]).is_well_ordered());
+        assert!(!depth(&[10.0, 0.0, 9.0], &[]).is_well_ordered());
+
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let mut quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        assert!(quotes.ill_ordered_depth().is_empty());
+        quotes
+            .instruments
+            .get_mut("8960258")
+            .unwrap()
+            .depth
+            .sell
+            .reverse();
+        quotes
+            .instruments
+            .get_mut("8960002")
+            .unwrap()
+            .depth
+            .buy
+            .reverse();
+        assert_eq!(
+            quotes.ill_ordered_depth(),
+            vec!["8960002".to_owned(), "8960258".to_owned()]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_bincode_round_trip() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let blob = quotes.to_bincode()?;
+        assert_eq!(Quotes::from_bincode(&blob)?, quotes);
+        assert!(Quotes::from_bincode(&blob[..blob.len() / 2]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_iter_sorted_by() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+
+        let by_volume: Vec<u64> = quotes
+            .iter_sorted_by(|q| std::cmp::Reverse(q.volume))
+            .map(|(_, q)| q.volume)
+            .collect();
+        assert_eq!(by_volume.len(), quotes.instruments.len());
+        assert!(by_volume.windows(2).all(|w| w[0] >= w[1]));
+
+        let by_symbol: Vec<&String> = quotes.iter_sorted_by(|_| ()).map(|(s, _)| s).collect();
+        assert!(by_symbol.windows(2).all(|w| w[0] < w[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_instrument_key() -> Result<(), Box<dyn Error>> {
+        let key = InstrumentKey::parse("NFO:NIFTY24SEP25000CE")?;
+        assert_eq!(key.exchange(), "NFO");
+        assert_eq!(key.tradingsymbol(), "NIFTY24SEP25000CE");
+        assert_eq!(key.to_string(), "NFO:NIFTY24SEP25000CE");
+        assert_eq!("BSE:A:B".parse::<InstrumentKey>()?.tradingsymbol(), "A:B");
+        for bad in [
+            "",
+            "INFY",
+            ":INFY",
+            "NSE:",
+            "nse:INFY",
+            "NSE:IN FY",
+            "408065",
+        ] {
+            assert_eq!(
+                InstrumentKey::parse(bad),
+                Err(ParseInstrumentKeyError(bad.to_owned()))
+            );
+        }
+
+        let mut quotes = Quotes::default();
+        quotes
+            .instruments
+            .insert("NSE:INFY".to_owned(), QuotesData::default());
+        let json = serde_json::to_string(&quotes)?;
+        let typed: QuotesTyped = serde_json::from_str(&json)?;
+        assert_eq!(typed, QuotesTyped::try_from(quotes.clone())?);
+        assert_eq!(serde_json::to_string(&typed)?, json);
+        assert_eq!(Quotes::from(typed), quotes);
+
+        // The mock keys are bare instrument tokens, so they're rejected.
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        assert!(serde_json::from_reader::<_, QuotesTyped>(jsonfile).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_instruments() {
+        let mut quotes = Quotes::default();
+        for (symbol, timestamp) in [
+            ("NSE:FRESH", "2021-06-08 15:45:56"),
+            ("NSE:EDGE", "2021-06-08 15:44:56"),
+            ("NSE:OLD", "2021-06-08 15:44:55"),
+            ("NSE:EMPTY", ""),
+            ("NSE:GARBAGE", "yesterday"),
+        ] {
+            let q = QuotesData {
+                timestamp: timestamp.to_owned(),
+                ..QuotesData::default()
+            };
+            quotes.instruments.insert(symbol.to_owned(), q);
+        }
+        let now = optional_naive_date_time_from_str::parse("2021-06-08 15:45:56").unwrap();
+        assert_eq!(
+            stale_instruments(&quotes, now, Duration::seconds(60)),
+            vec!["NSE:EMPTY", "NSE:GARBAGE", "NSE:OLD"]
+        );
+    }
+
+    #[test]
+    fn test_into_iterator_for_ref() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut count = 0;
+        for ((symbol, data), expected) in (&quotes).into_iter().zip(quotes.instruments.iter()) {
+            assert_eq!((symbol, data), expected);
+            count += 1;
+        }
+        assert_eq!(count, quotes.instruments.len());
+        for (symbol, data) in &quotes {
+            assert_eq!(symbol, &data.instrument_token.to_string());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_crossed_or_locked() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        assert!(crossed_or_locked(&quotes).is_empty());
+
+        let level = |price| OrderDepth {
+            price,
+            quantity: 1,
+            orders: 1,
+        };
+        let mut quotes = Quotes::default();
+        for (symbol, buy, sell) in [
+            ("NSE:NORMAL", vec![level(99.0)], vec![level(100.0)]),
+            ("NSE:LOCKED", vec![level(100.0)], vec![level(100.0)]),
+            ("NSE:CROSSED", vec![level(101.0)], vec![level(100.0)]),
+            ("NSE:BIDONLY", vec![level(101.0)], vec![]),
+            ("NSE:ASKPAD", vec![level(101.0)], vec![level(0.0)]),
+        ] {
+            let q = QuotesData {
+                depth: Depth { buy, sell },
+                ..QuotesData::default()
+            };
+            quotes.instruments.insert(symbol.to_owned(), q);
+        }
+        assert_eq!(
+            crossed_or_locked(&quotes),
+            vec![
+                ("NSE:CROSSED".to_owned(), BookState::Crossed),
+                ("NSE:LOCKED".to_owned(), BookState::Locked),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_update() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let original = quotes.instruments["8960002"].clone();
+
+        let mut q = original.clone();
+        q.apply_update(&QuotesDataPatch::default());
+        assert_eq!(q, original);
+
+        let patch: QuotesDataPatch = serde_json::from_str(
+            r#"{"last_price":1.25,"volume":42,"timestamp":"2021-06-08 15:46:00","depth":{"buy":[],"sell":[]}}"#,
+        )?;
+        q.apply_update(&patch);
+        assert_eq!(q.last_price, 1.25);
+        assert_eq!(q.volume, 42);
+        assert_eq!(q.timestamp, "2021-06-08 15:46:00");
+        assert_eq!(q.depth, Depth::default());
+        assert_eq!(
+            QuotesData {
+                last_price: original.last_price,
+                volume: original.volume,
+                timestamp: original.timestamp.clone(),
+                depth: original.depth.clone(),
+                ..q
+            },
+            original
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_totals_and_spread() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quote.json")?;
+        let quote: Quote = serde_json::from_reader(jsonfile)?;
+        let q = &quote.data.unwrap()["NSE:INFY"];
+        let mut data = QuotesData {
+            depth: q.depth.clone(),
+            ..QuotesData::default()
+        };
+
+        let buy: u64 = q.depth.buy.iter().map(|d| d.quantity).sum();
+        assert_eq!(data.total_buy_depth(), buy);
+        assert_eq!(data.total_sell_depth(), 5191);
+        assert_eq!(data.best_spread(), None);
+
+        data.depth.buy[0].price = 1412.5;
+        let ask = data.depth.sell[0].price;
+        assert_eq!(data.best_spread(), Some(ask - 1412.5));
+        data.depth.sell.clear();
+        assert_eq!(data.best_spread(), None);
+        assert_eq!(data.total_sell_depth(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dominant_side() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quote.json")?;
+        let quote: Quote = serde_json::from_reader(jsonfile)?;
+        let q = &quote.data.unwrap()["NSE:INFY"];
+        let mut data = QuotesData {
+            depth: q.depth.clone(),
+            ..QuotesData::default()
+        };
+        // The mock's bids are all zero padding.
+        assert_eq!(data.dominant_side(), Some(Side::Sell));
+
+        data.depth.buy[0].quantity = data.total_sell_depth() + 1;
+        assert_eq!(data.dominant_side(), Some(Side::Buy));
+        data.depth.buy[0].quantity -= 1;
+        assert_eq!(data.dominant_side(), None);
+        assert_eq!(QuotesData::default().dominant_side(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_totals_saturate() {
+        let level = |quantity| OrderDepth {
+            price: 100.0,
+            quantity,
+            orders: 1,
+        };
+        let mut data = QuotesData::default();
+        data.depth.buy = vec![level(u64::MAX - 1), level(1)];
+        data.depth.sell = vec![level(u64::MAX - 1), level(2), level(3)];
+        assert_eq!(data.total_buy_depth(), u64::MAX);
+        assert_eq!(data.total_sell_depth(), u64::MAX);
+        assert_eq!(saturating_sum([1, 2, 3]), 6);
+    }
+
+    #[test]
+    fn test_tradable_and_mode() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let q = &quotes.instruments["8960002"];
+        assert_eq!((q.tradable, q.mode.as_deref()), (None, None));
+
+        let mut value = serde_json::to_value(q)?;
+        value["tradable"] = false.into();
+        value["mode"] = "full".into();
+        let full: QuotesData = serde_json::from_value(value)?;
+        assert_eq!(full.tradable, Some(false));
+        assert_eq!(full.mode.as_deref(), Some("full"));
+        assert_eq!(
+            QuotesData {
+                tradable: None,
+                mode: None,
+                ..full
+            },
+            *q
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_reader_limited() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes = Quotes::from_reader_limited(jsonfile, 181)?;
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        assert_eq!(quotes, serde_json::from_reader(jsonfile)?);
+
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let err = Quotes::from_reader_limited(jsonfile, 180).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TooManyInstrumentsError>(),
+            Some(&TooManyInstrumentsError { limit: 180 })
+        );
+
+        // Only the map's shape is limited; other errors pass through.
+        let err = Quotes::from_reader_limited(&b"{\"NSE:INFY\": 1}"[..], 10).unwrap_err();
+        assert!(err.downcast_ref::<serde_json::Error>().is_some());
+        let err = Quotes::from_reader_limited(&b"{} trailing"[..], 10).unwrap_err();
+        assert!(err.downcast_ref::<serde_json::Error>().is_some());
+        assert_eq!(
+            Quotes::from_reader_limited(&b"{}"[..], 0)?,
+            Quotes::default()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_slice() -> Result<(), Box<dyn Error>> {
+        let bytes = std::fs::read("kiteconnect-mocks/quotes.json")?;
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let expected: Quotes = serde_json::from_reader(jsonfile)?;
+        assert_eq!(Quotes::from_slice(&bytes)?, expected);
+        assert!(Quotes::from_slice(b"[]").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_reader_logging_unknown() -> Result<(), Box<dyn Error>> {
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quotes.json")?;
+        let expected: Quotes = serde_json::from_str(&raw)?;
+        let (quotes, unknown) = Quotes::from_reader_logging_unknown(raw.as_bytes())?;
+        assert_eq!(quotes, expected);
+        assert!(unknown.is_empty());
+
+        let mut value: serde_json::Value = serde_json::from_str(&raw)?;
+        value["8960002"]["circuit_band"] = 5.into();
+        value["8960258"]["circuit_band"] = 10.into();
+        value["8960258"]["exchange_timestamp"] = "2024-09-02 16:36:27".into();
+        let json = serde_json::to_vec(&value)?;
+        let (quotes, unknown) = Quotes::from_reader_logging_unknown(&json[..])?;
+        assert_eq!(quotes, expected);
+        assert_eq!(unknown, ["circuit_band", "exchange_timestamp"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_reader_lenient() -> Result<(), Box<dyn Error>> {
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quotes.json")?;
+        let expected: Quotes = serde_json::from_str(&raw)?;
+        let (quotes, rejected) = Quotes::from_reader_lenient(raw.as_bytes());
+        assert_eq!(quotes, expected);
+        assert!(rejected.is_empty());
+
+        let mut value: serde_json::Value = serde_json::from_str(&raw)?;
+        value["8960002"]["last_price"] = "n/a".into();
+        value["8960258"] = 7.into();
+        let json = serde_json::to_vec(&value)?;
+        let (quotes, mut rejected) = Quotes::from_reader_lenient(&json[..]);
+        assert_eq!(quotes.instruments.len(), expected.instruments.len() - 2);
+        assert!(!quotes.instruments.contains_key("8960002"));
+        assert_eq!(
+            quotes.instruments["8960514"],
+            expected.instruments["8960514"]
+        );
+        rejected.sort();
+        let symbols: Vec<&str> = rejected.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(symbols, ["8960002", "8960258"]);
+        assert!(rejected[0].1.contains("invalid type"));
+
+        let (quotes, rejected) = Quotes::from_reader_lenient(&b"{\"NSE:INFY\": {} ,"[..]);
+        assert!(quotes.instruments.is_empty());
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(rejected[1].0, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json_array() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let expected: Quotes = quotes
+            .iter_sorted_by(|q| q.instrument_token)
+            .take(2)
+            .map(|(symbol, q)| (symbol.clone(), q.clone()))
+            .collect();
+
+        let rows: Vec<serde_json::Value> = expected
+            .iter_sorted_by(|q| q.instrument_token)
+            .map(|(symbol, q)| {
+                let mut row = serde_json::to_value(q).unwrap();
+                row["symbol"] = symbol.clone().into();
+                row
+            })
+            .collect();
+        let parsed = Quotes::from_json_array(&serde_json::to_string(&rows)?)?;
+        assert_eq!(parsed.instruments.len(), 2);
+        assert_eq!(parsed, expected);
+
+        assert!(Quotes::from_json_array("[{\"instrument_token\": 1}]").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_pretty_string() {
+        let level = |price, quantity, orders| OrderDepth {
+            price,
+            quantity,
+            orders,
+        };
+        let mut quotes = Quotes::default();
+        quotes.instruments.insert(
+            "NSE:INFY".to_string(),
+            QuotesData {
+                depth: Depth {
+                    buy: vec![level(1412.95, 5, 1), level(1412.9, 1200, 14)],
+                    sell: vec![level(1413.0, 40, 3)],
+                },
+                ..QuotesData::default()
+            },
+        );
+        assert!(quotes.depth_view("NSE:TCS").is_none());
+        let depth = quotes.depth_view("NSE:INFY").unwrap();
+        assert!(std::ptr::eq(depth, &quotes.instruments["NSE:INFY"].depth));
+
+        let expected = [
+            "orders  qty     bid |     ask qty orders",
+            "     1    5 1412.95 | 1413.00  40      3",
+            "    14 1200 1412.90 |                   ",
+        ];
+        assert_eq!(depth.to_pretty_string(), expected.join("\n"));
+        assert_eq!(
+            Depth::default().to_pretty_string(),
+            "orders qty bid | ask qty orders"
+        );
+    }
+
+    #[test]
+    fn test_field_coverage() {
+        let mut quotes = Quotes::default();
+        assert!(quotes.field_coverage().is_empty());
+
+        for (i, symbol) in ["NSE:INFY", "NSE:TCS", "NFO:NIFTY24SEPFUT", "NSE:SBIN"]
+            .into_iter()
+            .enumerate()
+        {
+            let q = QuotesData {
+                instrument_token: i as u64 + 1,
+                last_price: 100.0,
+                oi: if symbol.starts_with("NFO:") { 5000 } else { 0 },
+                ..QuotesData::default()
+            };
+            quotes.instruments.insert(symbol.to_owned(), q);
+        }
+        let coverage = quotes.field_coverage();
+        assert_eq!(coverage.len(), 19);
+        assert_eq!(coverage["last_price"], 1.0);
+        assert_eq!(coverage["oi"], 0.25);
+        assert_eq!(coverage["timestamp"], 0.0);
+        assert_eq!(coverage["depth"], 0.0);
+    }
+
+    #[test]
+    fn test_to_rows() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let rows = quotes.to_rows();
+        let columns = QuotesColumns::from_quotes(&quotes);
+        assert_eq!(rows.len(), columns.len());
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row.symbol, columns.symbols[i]);
+            assert_eq!(row.volume, columns.volumes[i]);
+            assert_eq!(row.close, columns.closes[i]);
+            let q = &quotes.instruments[&row.symbol];
+            assert_eq!(row.parsed_timestamp, q.parsed_timestamp());
+        }
+
+        let q = QuotesData {
+            timestamp: "2024-09-02 16:36:27".to_owned(),
+            last_trade_time: "not a time".to_owned(),
+            ..QuotesData::default()
+        };
+        let row = &Quotes::from_iter([("NSE:INFY".to_owned(), q)]).to_rows()[0];
+        assert_eq!(
+            row.parsed_timestamp,
+            NaiveDateTime::parse_from_str("2024-09-02 16:36:27", "%Y-%m-%d %H:%M:%S").ok()
+        );
+        assert_eq!(row.parsed_last_trade_time, None);
+        Ok(())
+    }
+
+    // Drops the whitespace outside string literals.
+    fn minify(json: &str) -> String {
+        let mut out = String::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        for c in json.chars() {
+            if in_string {
+                escaped = !escaped && c == '\\';
+                in_string = escaped || c != '"';
+            } else if c == '"' {
+                in_string = true;
+            } else if c.is_whitespace() {
+                continue;
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    #[test]
+    fn test_null_ohlc() -> Result<(), Box<dyn Error>> {
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quotes.json")?;
+        let mut value: serde_json::Value = serde_json::from_str(&raw)?;
+        value["8960002"]["ohlc"] = serde_json::Value::Null;
+        value["8960258"].as_object_mut().unwrap().remove("ohlc");
+        let quotes: Quotes = serde_json::from_value(value)?;
+        assert_eq!(quotes.instruments["8960002"].ohlc, None);
+        assert_eq!(quotes.instruments["8960258"].ohlc, None);
+        assert!(quotes.instruments["8960514"].ohlc.is_some());
+
+        let q = &quotes.instruments["8960002"];
+        let canonical = q.to_canonical_json();
+        assert!(canonical.contains(r#""ohlc":null,"depth""#));
+        assert_eq!(&serde_json::from_str::<QuotesData>(&canonical)?, q);
+        let row = &quotes.to_rows()[0];
+        assert_eq!(
+            row.open.is_none(),
+            quotes.get(&row.symbol).unwrap().ohlc.is_none()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_canonical_json() -> Result<(), Box<dyn Error>> {
+        let raw = std::fs::read_to_string("kiteconnect-mocks/quote.json")?;
+        let quote: Quote = serde_json::from_str(&raw)?;
+        let data = &quote.data.unwrap()["NSE:INFY"];
+        let q = QuotesData {
+            instrument_token: data.instrument_token,
+            timestamp: "2021-06-08 15:45:56".to_owned(),
+            last_trade_time: "2021-06-08 15:45:52".to_owned(),
+            last_price: data.last_price,
+            last_quantity: data.last_quantity,
+            buy_quantity: data.buy_quantity,
+            sell_quantity: data.sell_quantity,
+            volume: data.volume,
+            average_price: data.average_price,
+            oi: data.oi,
+            oi_day_high: data.oi_day_high,
+            oi_day_low: data.oi_day_low,
+            net_change: data.net_change,
+            lower_circuit_limit: data.lower_circuit_limit,
+            upper_circuit_limit: data.upper_circuit_limit,
+            ohlc: Some(data.ohlc.clone()),
+            depth: data.depth.clone(),
+            ..QuotesData::default()
+        };
+
+        // The captured object, exactly as Kite sent it.
+        let start = raw.find(r#""NSE:INFY": "#).unwrap() + r#""NSE:INFY": "#.len();
+        let end = raw[start..].find("\n        }").unwrap() + start + "\n        }".len();
+        let captured = minify(&raw[start..end]);
+        assert!(captured.contains(r#""net_change":0,"#));
+        assert_eq!(q.to_canonical_json(), captured);
+
+        let canonical: QuotesData = serde_json::from_str(&q.to_canonical_json())?;
+        assert_eq!(canonical, q);
+
+        let flagged = QuotesData {
+            tradable: Some(true),
+            mode: Some("full".to_owned()),
+            last_price: f64::NAN,
+            ..QuotesData::default()
+        };
+        let json = flagged.to_canonical_json();
+        assert!(json.contains(r#""last_price":null,"#));
+        assert!(json.ends_with(r#"]},"tradable":true,"mode":"full"}"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_timestamps_parse_to_none() {
+        let now = optional_naive_date_time_from_str::parse("2021-06-08 15:45:56").unwrap();
+        for empty in ["", "  "] {
+            let q = QuotesData {
+                timestamp: empty.to_owned(),
+                last_trade_time: empty.to_owned(),
+                ..QuotesData::default()
+            };
+            assert_eq!(q.parsed_timestamp(), None);
+            assert_eq!(q.parsed_last_trade_time(), None);
+
+            let quotes: Quotes = [("NSE:PREOPEN".to_owned(), q)].into_iter().collect();
+            let row = &quotes.to_rows()[0];
+            assert_eq!(
+                (row.parsed_timestamp, row.parsed_last_trade_time),
+                (None, None)
+            );
+            assert_eq!(
+                stale_instruments(&quotes, now, Duration::minutes(5)),
+                ["NSE:PREOPEN"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_quotes_display_and_summary_table() {
+        let quote = |last_price, net_change, volume| QuotesData {
+            last_price,
+            net_change,
+            volume,
+            ..QuotesData::default()
+        };
+        let quotes = Quotes::from_iter([
+            ("NSE:TCS".to_owned(), quote(4350.5, -12.25, 1200)),
+            ("NSE:INFY".to_owned(), quote(1412.95, 0.0, 7360198)),
+        ]);
+
+        assert_eq!(
+            quotes.to_string(),
+            "NSE:INFY last_price=1412.95 net_change=0 volume=7360198\n\
+             NSE:TCS last_price=4350.5 net_change=-12.25 volume=1200"
+        );
+        let expected = [
+            "symbol    last_price  net_change   volume",
+            "NSE:INFY     1412.95        0.00  7360198",
+            "NSE:TCS      4350.50      -12.25     1200",
+        ];
+        assert_eq!(quotes.summary_table(), expected.join("\n"));
+        assert_eq!(Quotes::default().to_string(), "");
+    }
+
+    #[test]
+    fn test_quotes_get() {
+        let mut quotes = Quotes::default();
+        let infy = QuotesData {
+            instrument_token: 408065,
+            ..QuotesData::default()
+        };
+        quotes
+            .instruments
+            .insert("NSE:INFY".to_owned(), infy.clone());
+
+        assert_eq!(quotes.get("NSE:INFY"), Some(&infy));
+        assert_eq!(quotes.get("nse:infy"), None);
+        assert_eq!(quotes.get_ignore_case("nse:infy"), Some(&infy));
+        assert_eq!(quotes.get_ignore_case("Nse:Infy"), Some(&infy));
+        assert_eq!(quotes.get_ignore_case("NSE:TCS"), None);
+    }
+
+    #[test]
+    fn test_sweep_cost() {
+        let level = |price, quantity| OrderDepth {
+            price,
+            quantity,
+            orders: 1,
+        };
+        let q = QuotesData {
+            depth: Depth {
+                buy: vec![level(99.0, 10), level(98.0, 20), level(0.0, 0)],
+                sell: vec![level(101.0, 10), level(102.0, 30), level(0.0, 0)],
+            },
+            ..QuotesData::default()
+        };
+
+        assert_eq!(q.sweep_cost(Side::Buy, 5), Some(101.0));
+        assert_eq!(q.sweep_cost(Side::Buy, 10), Some(101.0));
+        // Spans two levels: 10 @ 101 + 10 @ 102.
+        assert_eq!(q.sweep_cost(Side::Buy, 20), Some(101.5));
+        assert_eq!(q.sweep_cost(Side::Sell, 30), Some((990.0 + 1960.0) / 30.0));
+        // Exceeds the available depth.
+        assert_eq!(q.sweep_cost(Side::Buy, 41), None);
+        assert_eq!(q.sweep_cost(Side::Sell, 0), None);
+        assert_eq!(QuotesData::default().sweep_cost(Side::Buy, 1), None);
+    }
+
+    #[test]
+    fn test_dedup_by_token() {
+        let mut quotes = Quotes::default();
+        for (symbol, token, timestamp) in [
+            ("NSE:INFY", 408065, "2021-06-08 15:45:56"),
+            ("INFY-ALIAS", 408065, "2021-06-08 15:45:57"),
+            ("INFY-BROKEN", 408065, "garbage"),
+            ("NSE:TCS", 2953217, ""),
+            ("TCS-ALIAS", 2953217, "2021-06-08 15:45:00"),
+            ("NSE:SBIN", 779521, "2021-06-08 15:45:00"),
+            ("B-TIE", 1, "2021-06-08 15:45:00"),
+            ("A-TIE", 1, "2021-06-08 15:45:00"),
+        ] {
+            let q = QuotesData {
+                instrument_token: token,
+                timestamp: timestamp.to_owned(),
+                ..QuotesData::default()
+            };
+            quotes.instruments.insert(symbol.to_owned(), q);
+        }
+
+        assert_eq!(quotes.dedup_by_token(), 4);
+        let mut kept: Vec<&str> = quotes.instruments.keys().map(String::as_str).collect();
+        kept.sort();
+        assert_eq!(kept, ["A-TIE", "INFY-ALIAS", "NSE:SBIN", "TCS-ALIAS"]);
+        assert_eq!(quotes.dedup_by_token(), 0);
+    }
+
+    #[test]
+    fn test_depth_absent() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+        let mut value = serde_json::to_value(&quotes.instruments["8960002"])?;
+        value.as_object_mut().unwrap().remove("depth");
+
+        let q: QuotesData = serde_json::from_value(value)?;
+        assert_eq!(q.depth, Depth::default());
+        assert_eq!(q.best_bid_ask(), None);
+        assert_eq!(q.sweep_cost(Side::Buy, 1), None);
+        assert_eq!((q.total_buy_depth(), q.total_sell_depth()), (0, 0));
+        assert!(q.depth.is_well_ordered());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_from_iter() {
+        let quotes: Quotes = ["NSE:INFY", "NSE:TCS", "NSE:INFY"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, symbol)| {
+                let q = QuotesData {
+                    instrument_token: i as u64,
+                    ..QuotesData::default()
+                };
+                (symbol.to_owned(), q)
+            })
+            .collect();
+        assert_eq!(quotes.instruments.len(), 2);
+        // Later pairs win, as with `HashMap`.
+        assert_eq!(quotes.instruments["NSE:INFY"].instrument_token, 2);
+        assert_eq!(Quotes::from_iter([]), Quotes::default());
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct TimestampHolder {
+        #[serde(with = "optional_naive_date_time_from_str")]
+        timestamp: Option<NaiveDateTime>,
+    }
+
+    #[test]
+    fn test_optional_naive_date_time_from_str() {
+        let parse = |raw: &str| serde_json::from_str::<TimestampHolder>(raw).map(|h| h.timestamp);
+
+        assert_eq!(parse(r#"{"timestamp":null}"#).unwrap(), None);
+        assert_eq!(parse(r#"{"timestamp":""}"#).unwrap(), None);
+        assert_eq!(
+            parse(r#"{"timestamp":"2021-06-08 15:45:56"}"#).unwrap(),
+            NaiveDate::from_ymd_opt(2021, 6, 8)
+                .unwrap()
+                .and_hms_opt(15, 45, 56)
+        );
+        assert_eq!(
+            parse(r#"{"timestamp":"2021-06-08 15:45:56.123"}"#).unwrap(),
+            NaiveDate::from_ymd_opt(2021, 6, 8)
+                .unwrap()
+                .and_hms_milli_opt(15, 45, 56, 123)
+        );
+        for raw in [
+            r#"{"timestamp":"2021-06-08 15:45:56"}"#,
+            r#"{"timestamp":"2021-06-08 15:45:56.123"}"#,
+            r#"{"timestamp":null}"#,
+        ] {
+            let holder: TimestampHolder = serde_json::from_str(raw).unwrap();
+            assert_eq!(serde_json::to_string(&holder).unwrap(), raw);
+        }
+        assert!(parse(r#"{"timestamp":1623147356}"#).is_err());
+        assert!(parse(r#"{"timestamp":{"date":"2021-06-08"}}"#).is_err());
+        assert!(parse(r#"{"timestamp":"not a timestamp"}"#).is_err());
+    }
+
+    #[test]
+    fn test_quotes_merge() -> Result<(), Box<dyn Error>> {
+        let jsonfile = read_json_from_file("kiteconnect-mocks/quotes.json")?;
+        let quotes: Quotes = serde_json::from_reader(jsonfile)?;
+
+        let mut older = quotes.clone();
+        older.instruments.remove("8960002");
+        for q in older.instruments.values_mut() {
+            q.timestamp = "2024-09-02 09:15:00".to_owned();
+            q.last_price = 0.0;
+        }
+
+        let mut merged = quotes.clone();
+        assert_eq!(merged.merge(older.clone(), MergePolicy::KeepNewest), 0);
+        assert_eq!(merged, quotes);
+
+        let mut merged = older.clone();
+        assert_eq!(merged.merge(quotes.clone(), MergePolicy::KeepNewest), 180);
+        assert_eq!(merged, quotes);
+
+        let mut merged = quotes.clone();
+        assert_eq!(merged.merge(older.clone(), MergePolicy::Overwrite), 180);
+        assert_eq!(merged.instruments.len(), 181);
+        assert_eq!(merged.instruments["8960002"], quotes.instruments["8960002"]);
+        assert_eq!(merged.instruments["8960258"], older.instruments["8960258"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_to_tick() {
+        assert_eq!(round_to_tick(1412.93, 0.05), 1412.95);
+        assert_eq!(round_to_tick(1412.92, 0.05), 1412.9);
+        assert_eq!(round_to_tick(1412.95, 0.05), 1412.95);
+        assert_eq!(round_to_tick(25335.53, 0.1), 25335.5);
+        assert_eq!(round_to_tick(101.4, 1.0), 101.0);
+        assert_eq!(round_to_tick(0.0, 0.05), 0.0);
+        assert_eq!(round_to_tick(1412.93, 0.0), 1412.93);
+
+        let mut quotes = Quotes::default();
+        quotes.instruments.insert(
+            "NSE:INFY".to_owned(),
+            QuotesData {
+                last_price: 1412.97,
+                ohlc: Some(OhlcInner {
+                    open: 1396.01,
+                    high: 1421.76,
+                    low: 1395.54,
+                    close: 1389.66,
+                }),
+                depth: Depth {
+                    buy: vec![OrderDepth {
+                        price: 1412.91,
+                        quantity: 10,
+                        orders: 1,
+                    }],
+                    sell: vec![OrderDepth::default()],
+                },
+                ..QuotesData::default()
+            },
+        );
+        normalize_prices(&mut quotes, 0.05);
+        let q = &quotes.instruments["NSE:INFY"];
+        assert_eq!(q.last_price, 1412.95);
+        assert_eq!(
+            q.ohlc,
+            Some(OhlcInner {
+                open: 1396.0,
+                high: 1421.75,
+                low: 1395.55,
+                close: 1389.65,
+            })
+        );
+        assert_eq!(q.depth.buy[0].price, 1412.9);
+        assert_eq!(q.depth.sell[0].price, 0.0);
+    }
+
+    #[test]
+    fn test_scale_prices() {
+        let mut quotes = Quotes::default();
+        quotes.instruments.insert(
+            "NSE:INFY".to_owned(),
+            QuotesData {
+                last_price: 141295.0,
+                average_price: 141247.0,
+                net_change: 5.0,
+                lower_circuit_limit: 125070.0,
+                upper_circuit_limit: 152860.0,
+                ohlc: Some(OhlcInner {
+                    open: 139600.0,
+                    high: 142175.0,
+                    low: 139555.0,
+                    close: 138965.0,
+                }),
+                depth: Depth {
+                    buy: vec![OrderDepth {
+                        price: 141290.0,
+                        quantity: 10,
+                        orders: 1,
+                    }],
+                    sell: vec![],
+                },
+                volume: 7360198,
+                ..QuotesData::default()
+            },
+        );
+        quotes.scale_prices(0.01);
+
+        let q = &quotes.instruments["NSE:INFY"];
+        assert!((q.last_price - 1412.95).abs() < 1e-9);
+        assert!((q.average_price - 1412.47).abs() < 1e-9);
+        assert!((q.upper_circuit_limit - 1528.6).abs() < 1e-9);
+        assert!((q.ohlc.as_ref().unwrap().close - 1389.65).abs() < 1e-9);
+        assert!((q.depth.buy[0].price - 1412.9).abs() < 1e-9);
+        assert_eq!(q.net_change, 5.0);
+        assert_eq!(q.volume, 7360198);
+    }
+}